@@ -0,0 +1,59 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rand_chacha::ChaCha20Rng;
+use rand::{Rng, SeedableRng};
+use subquote::{
+  io::Unit,
+  builder::{self, MarkovDict, QuoteConfig}
+};
+
+const WORDS: [&str; 24] = [
+  "I", "you", "we", "They", "He", "She", "told", "never", "want", "to", "go", "home", "the", "a", "night",
+  "morning", "door", "is", "open", "now", "here", "there", "Well", "Maybe"
+];
+
+// a deterministic subtitle with enough cues and vocabulary to resemble a feature length one
+fn fixture(cues: usize) -> String {
+  let mut rng = ChaCha20Rng::seed_from_u64(42);
+  (0..cues)
+    .map(|index| {
+      let (start, end) = (index * 3, index * 3 + 2);
+      let line: Vec<&str> = (0..rng.gen_range(3, 12)).map(|_| WORDS[rng.gen_range(0, WORDS.len())]).collect();
+      format!(
+        "{}\n{:02}:{:02}:{:02},000 --> {:02}:{:02}:{:02},000\n{}.\n",
+        index + 1,
+        start / 3600, start / 60 % 60, start % 60,
+        end / 3600, end / 60 % 60, end % 60,
+        line.join(" ")
+      )
+    })
+    .collect::<Vec<String>>()
+    .join("\n")
+}
+
+fn construction(c: &mut Criterion) {
+  let subtitle = fixture(1500);
+  c.bench_function("build_dict word", |b| b.iter(|| builder::build_dict(black_box(&subtitle), &Unit::Word, false)));
+  c.bench_function("build_dict char", |b| b.iter(|| builder::build_dict(black_box(&subtitle), &Unit::Grapheme, false)));
+}
+
+// the same dictionary from each cache format
+fn loading(c: &mut Criterion) {
+  let dict = builder::build_dict(&fixture(1500), &Unit::Word, false);
+  let (mut json, mut binary) = (Vec::new(), Vec::new());
+  dict.write_to(&mut json).unwrap();
+  dict.write_binary(&mut binary).unwrap();
+  c.bench_function("load json", |b| b.iter(|| MarkovDict::read_from(black_box(json.as_slice())).unwrap()));
+  c.bench_function("load binary", |b| b.iter(|| MarkovDict::read_binary(black_box(binary.as_slice())).unwrap()));
+}
+
+fn generation(c: &mut Criterion) {
+  let dict = builder::build_dict(&fixture(1500), &Unit::Word, false);
+  let mut seed = 0;
+  c.bench_function("generate", |b| b.iter(|| {
+    seed += 1;
+    builder::generate(black_box(&dict), &QuoteConfig::new().length(12).seed(seed))
+  }));
+}
+
+criterion_group!(benches, construction, loading, generation);
+criterion_main!(benches);
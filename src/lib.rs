@@ -1,15 +1,141 @@
 pub mod io {
+  use std::collections::HashSet;
   use std::env;
   use std::fmt::{self, Debug, Display, Formatter};
-  use std::path;
+  use std::path::{Path, PathBuf};
   use std::fs;
+  use std::io::ErrorKind;
+  #[cfg(feature = "cli")]
+  use std::num::IntErrorKind;
+  use std::time::Duration;
+  #[cfg(feature = "cli")]
   use getopts::Options;
+  use regex::Regex;
+  use encoding_rs::Encoding;
+
+  // longer quotes are refused by `parse_args`, since generation goes on as long as the chain allows
+  pub const MAX_QUOTE_LENGTH: i32 = 1000;
+  // the defaults of the command line, which the library falls back on as well
+  pub const DEFAULT_WORD_LENGTH: i32 = 5;
+  pub const DEFAULT_GRAPHEME_LENGTH: i32 = 25;
+  pub const DEFAULT_UNIT: Unit = Unit::Word;
+  pub const DEFAULT_VERBOSITY: bool = false;
+  const TEMPLATE_PLACEHOLDERS: [&str; 5] = ["quote", "source", "length", "unit", "timecode"];
 
+  // what stops parsing short of arguments, only an error being an actual failure; help holds the usage
+  #[cfg(feature = "cli")]
+  #[derive(Debug, PartialEq)]
   pub enum ParseOutcome {
     Error(String),
-    Help
+    Help(String),
+    Version
+  }
+
+  #[cfg(feature = "cli")]
+  impl Display for ParseOutcome {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+      match self {
+        ParseOutcome::Error(err) => write!(f, "{}", err),
+        ParseOutcome::Help(usage) => write!(f, "{}", usage),
+        ParseOutcome::Version => write!(f, "{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+      }
+    }
+  }
+
+  #[cfg(feature = "cli")]
+  impl std::error::Error for ParseOutcome {}
+
+  #[derive(Clone, Copy, Debug, PartialEq)]
+  pub enum Command {
+    Build,
+    Generate,
+    Stats,
+    Verify
+  }
+
+  impl Display for Command {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+      match self {
+        Command::Build => write!(f, "build"),
+        Command::Generate => write!(f, "gen"),
+        Command::Stats => write!(f, "stats"),
+        Command::Verify => write!(f, "verify")
+      }
+    }
+  }
+
+  // casing of the printed quote only, dictionaries are left untouched
+  #[derive(Clone, Copy, Debug, PartialEq)]
+  pub enum Case {
+    Lower,
+    Upper,
+    Title
+  }
+
+  impl Case {
+    pub fn apply(&self, text: &str) -> String {
+      match self {
+        Case::Lower => text.to_lowercase(),
+        Case::Upper => text.to_uppercase(),
+        Case::Title => {
+          let mut titled = String::with_capacity(text.len());
+          let mut word_start = true;
+          for c in text.chars() {
+            match word_start {
+              true => titled.push_str(&titlecase(c)),
+              false => titled.extend(c.to_lowercase())
+            }
+            word_start = c.is_whitespace();
+          }
+          titled
+        }
+      }
+    }
+  }
+
+  // a letter starting a word isn't its uppercase when it's a digraph ("ǳ" becomes "ǲ"), uppercased
+  // into several letters ("ß" becomes "Ss"), Greek with an iota subscript, or Georgian (left as is)
+  pub(crate) fn titlecase(c: char) -> String {
+    let titled = match c {
+      'ß' => "Ss",
+      '\u{1c4}'..='\u{1c6}' => "\u{1c5}",
+      '\u{1c7}'..='\u{1c9}' => "\u{1c8}",
+      '\u{1ca}'..='\u{1cc}' => "\u{1cb}",
+      '\u{1f1}'..='\u{1f3}' => "\u{1f2}",
+      '\u{587}' => "\u{535}\u{582}",
+      '\u{10d0}'..='\u{10fa}' | '\u{10fd}'..='\u{10ff}' => return c.to_string(),
+      // the titlecase of each of these blocks of eight is the last half of it
+      '\u{1f80}'..='\u{1faf}' => return char::from_u32(c as u32 | 0x8).unwrap_or(c).to_string(),
+      '\u{1fb2}' => "\u{1fba}\u{345}",
+      '\u{1fb3}' | '\u{1fbc}' => "\u{1fbc}",
+      '\u{1fb4}' => "\u{386}\u{345}",
+      '\u{1fb7}' => "\u{391}\u{342}\u{345}",
+      '\u{1fc2}' => "\u{1fca}\u{345}",
+      '\u{1fc3}' | '\u{1fcc}' => "\u{1fcc}",
+      '\u{1fc4}' => "\u{389}\u{345}",
+      '\u{1fc7}' => "\u{397}\u{342}\u{345}",
+      '\u{1ff2}' => "\u{1ffa}\u{345}",
+      '\u{1ff3}' | '\u{1ffc}' => "\u{1ffc}",
+      '\u{1ff4}' => "\u{38f}\u{345}",
+      '\u{1ff7}' => "\u{3a9}\u{342}\u{345}",
+      '\u{fb00}' => "Ff",
+      '\u{fb01}' => "Fi",
+      '\u{fb02}' => "Fl",
+      '\u{fb03}' => "Ffi",
+      '\u{fb04}' => "Ffl",
+      '\u{fb05}' | '\u{fb06}' => "St",
+      '\u{fb13}' => "\u{544}\u{576}",
+      '\u{fb14}' => "\u{544}\u{565}",
+      '\u{fb15}' => "\u{544}\u{56b}",
+      '\u{fb16}' => "\u{54e}\u{576}",
+      '\u{fb17}' => "\u{544}\u{56d}",
+      _ => return c.to_uppercase().collect()
+    };
+    String::from(titled)
   }
 
+  // only parsing builds arguments, the defaults being shared with the library without the cli feature
+  #[cfg_attr(not(feature = "cli"), allow(dead_code))]
   enum ArgProvided<T> {
     Yes(T),
     No(T)
@@ -34,6 +160,7 @@ pub mod io {
     }
   }
 
+  #[derive(PartialEq)]
   pub enum Unit {
     Grapheme,
     Word
@@ -57,31 +184,192 @@ pub mod io {
     }
   }
 
+  #[derive(Clone, Copy, Debug, PartialEq)]
+  pub enum Format {
+    Srt,
+    Vtt,
+    Ass,
+    Text
+  }
+
+  impl Display for Format {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+      match self {
+        Format::Srt => write!(f, "srt"),
+        Format::Vtt => write!(f, "vtt"),
+        Format::Ass => write!(f, "ass"),
+        Format::Text => write!(f, "text")
+      }
+    }
+  }
+
+  // how dictionaries are cached, JSON being readable and the binary encoding (CBOR) faster to load
+  #[derive(Clone, Copy, Debug, PartialEq)]
+  pub enum DictFormat {
+    Json,
+    Binary
+  }
+
+  impl Display for DictFormat {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+      match self {
+        DictFormat::Json => write!(f, "json"),
+        DictFormat::Binary => write!(f, "binary")
+      }
+    }
+  }
+
+  // how lines are split into words, the pattern of a regex matching the separators
+  #[derive(Clone, Debug, Default)]
+  pub enum Tokenizer {
+    #[default]
+    Whitespace,
+    UnicodeWords,
+    Pattern(Regex)
+  }
+
+  impl Display for Tokenizer {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+      match self {
+        Tokenizer::Whitespace => write!(f, "whitespace"),
+        Tokenizer::UnicodeWords => write!(f, "unicode-words"),
+        Tokenizer::Pattern(reg) => write!(f, "regex:{}", reg.as_str())
+      }
+    }
+  }
+
   pub struct SafeArguments {
-    pub subtitle: String,
+    pub subtitles: Vec<PathBuf>,
     pub quote_length: i32,
     pub verbosity: bool,
     pub unit: Unit,
-    pub cache_directory: String,
-    pub no_cache: bool
+    pub extra_units: Vec<Unit>,
+    pub cache_directory: PathBuf,
+    pub no_cache: bool,
+    pub separator: String,
+    pub extract_cmd: Option<String>,
+    pub max_chars: Option<usize>,
+    pub no_immediate_repeat: bool,
+    pub dump_dialogue: bool,
+    pub keep_punctuation: bool,
+    pub min_length: i32,
+    pub seed: Option<u64>,
+    pub template: Option<String>,
+    pub continue_phrase: Option<String>,
+    pub max_quote_length: Option<i32>,
+    pub no_start_words: Vec<String>,
+    pub encoding: Option<&'static Encoding>,
+    pub must_include: Option<String>,
+    pub from_time: Option<Duration>,
+    pub to_time: Option<Duration>,
+    pub command: Command,
+    pub with_timecode: bool,
+    pub case: Option<Case>,
+    pub append_cache: Option<PathBuf>,
+    pub sentence_boundaries: bool,
+    pub format: Option<Format>,
+    pub smart_start: bool,
+    pub repl: bool,
+    pub trace: bool,
+    pub speaker: Option<String>,
+    pub tokenizer: Tokenizer,
+    pub estimate_variety: bool,
+    pub skip_cues: Vec<(u64, u64)>,
+    pub prune: bool,
+    pub json_dict_out: Option<PathBuf>,
+    pub no_append: bool,
+    pub no_capitalize: bool,
+    pub keep_duplicates: bool,
+    pub recursive: bool,
+    pub glob: Option<String>,
+    pub stop_at: Vec<String>,
+    pub longest: bool,
+    pub min_diversity: Option<f64>,
+    pub no_repeat_window: Option<usize>,
+    pub cache_file: Option<PathBuf>,
+    pub output_file: Option<PathBuf>,
+    pub append_output: bool,
+    pub keep_captions: bool,
+    pub cue_boundaries: bool,
+    pub preview: Option<usize>,
+    pub pick: Option<usize>,
+    pub drop_spaces: bool,
+    pub successors: Option<String>,
+    pub end_word: Option<String>,
+    pub dict_format: DictFormat
   }
 
   impl SafeArguments {
-    fn new(
-      subtitle: String,
-      quote_length: i32,
-      verbosity: bool,
-      cache_directory: String,
-      unit: Unit,
-      no_cache: bool
-    ) -> Self {
+    // the defaults, reading `subtitles`
+    pub fn with_subtitles(subtitles: Vec<PathBuf>) -> Self {
+      Self { subtitles, ..Self::default() }
+    }
+  }
+
+  // the CLI defaults without a cache, for library users and tests skipping parsing and validation,
+  // the fields being public to change the others
+  impl Default for SafeArguments {
+    fn default() -> Self {
+      let unit = UnsafeArguments::get_default_unit();
       Self {
-        subtitle,
-        quote_length,
-        verbosity,
-        cache_directory,
-        unit,
-        no_cache
+        subtitles: Vec::new(),
+        quote_length: UnsafeArguments::get_default_quote_length(Some(unit.clone())),
+        verbosity: UnsafeArguments::get_default_verbosity(),
+        cache_directory: PathBuf::new(),
+        unit: unit.clone(),
+        extra_units: Vec::new(),
+        no_cache: true,
+        separator: UnsafeArguments::get_default_separator(Some(unit)),
+        extract_cmd: None,
+        max_chars: None,
+        no_immediate_repeat: UnsafeArguments::get_default_no_immediate_repeat(),
+        dump_dialogue: UnsafeArguments::get_default_dump_dialogue(),
+        keep_punctuation: UnsafeArguments::get_default_keep_punctuation(),
+        min_length: UnsafeArguments::get_default_min_length(),
+        seed: None,
+        template: None,
+        continue_phrase: None,
+        max_quote_length: None,
+        no_start_words: Vec::new(),
+        encoding: None,
+        must_include: None,
+        from_time: None,
+        to_time: None,
+        command: Command::Generate,
+        with_timecode: UnsafeArguments::get_default_with_timecode(),
+        case: None,
+        append_cache: None,
+        sentence_boundaries: UnsafeArguments::get_default_sentence_boundaries(),
+        format: None,
+        smart_start: UnsafeArguments::get_default_smart_start(),
+        repl: UnsafeArguments::get_default_repl(),
+        trace: UnsafeArguments::get_default_trace(),
+        speaker: None,
+        tokenizer: Tokenizer::default(),
+        estimate_variety: UnsafeArguments::get_default_estimate_variety(),
+        skip_cues: Vec::new(),
+        prune: UnsafeArguments::get_default_prune(),
+        json_dict_out: None,
+        no_append: UnsafeArguments::get_default_no_append(),
+        no_capitalize: UnsafeArguments::get_default_no_capitalize(),
+        keep_duplicates: UnsafeArguments::get_default_keep_duplicates(),
+        recursive: UnsafeArguments::get_default_recursive(),
+        glob: None,
+        stop_at: Vec::new(),
+        longest: UnsafeArguments::get_default_longest(),
+        min_diversity: None,
+        no_repeat_window: None,
+        cache_file: None,
+        output_file: None,
+        append_output: UnsafeArguments::get_default_append_output(),
+        keep_captions: UnsafeArguments::get_default_keep_captions(),
+        cue_boundaries: UnsafeArguments::get_default_cue_boundaries(),
+        preview: None,
+        pick: None,
+        drop_spaces: UnsafeArguments::get_default_drop_spaces(),
+        successors: None,
+        end_word: None,
+        dict_format: UnsafeArguments::get_default_dict_format()
       }
     }
   }
@@ -90,91 +378,355 @@ pub mod io {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
       write!(
         f,
-        "UnsafeArguments {{ subtitle: {}, quote_length: {}, verbosity: {}, cache_directory: {}, unit: {}, no_cache: {} }}",
-        self.subtitle,
+        "UnsafeArguments {{ subtitles: {:?}, quote_length: {}, verbosity: {}, cache_directory: {}, unit: {}, extra_units: [{}], no_cache: {}, separator: {:?}, extract_cmd: {:?}, max_chars: {:?}, no_immediate_repeat: {}, dump_dialogue: {}, keep_punctuation: {}, min_length: {}, seed: {:?}, template: {:?}, continue_phrase: {:?}, max_quote_length: {:?}, no_start_words: {:?}, encoding: {:?}, must_include: {:?}, from_time: {:?}, to_time: {:?}, command: {:?}, with_timecode: {}, case: {:?}, append_cache: {:?}, sentence_boundaries: {}, format: {:?}, smart_start: {}, repl: {}, trace: {}, speaker: {:?}, tokenizer: {:?}, estimate_variety: {}, skip_cues: {:?}, prune: {}, json_dict_out: {:?}, no_append: {}, no_capitalize: {}, keep_duplicates: {}, recursive: {}, glob: {:?}, stop_at: {:?}, longest: {}, min_diversity: {:?}, no_repeat_window: {:?}, cache_file: {:?}, output_file: {:?}, append_output: {}, keep_captions: {}, cue_boundaries: {}, preview: {:?}, pick: {:?}, drop_spaces: {}, successors: {:?}, end_word: {:?}, dict_format: {} }}",
+        self.subtitles,
         self.quote_length,
         self.verbosity,
-        self.cache_directory,
+        self.cache_directory.display(),
         self.unit,
-        self.no_cache
+        self.extra_units.iter().map(|unit| unit.to_string()).collect::<Vec<String>>().join(", "),
+        self.no_cache,
+        self.separator,
+        self.extract_cmd,
+        self.max_chars,
+        self.no_immediate_repeat,
+        self.dump_dialogue,
+        self.keep_punctuation,
+        self.min_length,
+        self.seed,
+        self.template,
+        self.continue_phrase,
+        self.max_quote_length,
+        self.no_start_words,
+        self.encoding.map(Encoding::name),
+        self.must_include,
+        self.from_time,
+        self.to_time,
+        self.command,
+        self.with_timecode,
+        self.case,
+        self.append_cache,
+        self.sentence_boundaries,
+        self.format,
+        self.smart_start,
+        self.repl,
+        self.trace,
+        self.speaker,
+        self.tokenizer,
+        self.estimate_variety,
+        self.skip_cues,
+        self.prune,
+        self.json_dict_out,
+        self.no_append,
+        self.no_capitalize,
+        self.keep_duplicates,
+        self.recursive,
+        self.glob,
+        self.stop_at,
+        self.longest,
+        self.min_diversity,
+        self.no_repeat_window,
+        self.cache_file,
+        self.output_file,
+        self.append_output,
+        self.keep_captions,
+        self.cue_boundaries,
+        self.preview,
+        self.pick,
+        self.drop_spaces,
+        self.successors,
+        self.end_word,
+        self.dict_format
       )
     }
   }
 
   pub struct UnsafeArguments {
-    subtitle: String,
+    subtitles: Vec<PathBuf>,
     quote_length: ArgProvided<i32>,
     verbosity: ArgProvided<bool>,
     unit: ArgProvided<Unit>,
-    cache_directory: ArgProvided<String>,
-    no_cache: ArgProvided<bool>
+    extra_units: Vec<Unit>,
+    cache_directory: ArgProvided<PathBuf>,
+    no_cache: ArgProvided<bool>,
+    separator: ArgProvided<String>,
+    extract_cmd: Option<String>,
+    max_chars: Option<usize>,
+    no_immediate_repeat: ArgProvided<bool>,
+    dump_dialogue: ArgProvided<bool>,
+    keep_punctuation: ArgProvided<bool>,
+    min_length: ArgProvided<i32>,
+    seed: Option<u64>,
+    template: Option<String>,
+    continue_phrase: Option<String>,
+    max_quote_length: Option<i32>,
+    no_start_words: Option<PathBuf>,
+    encoding: Option<&'static Encoding>,
+    must_include: Option<String>,
+    from_time: Option<Duration>,
+    to_time: Option<Duration>,
+    command: Command,
+    with_timecode: ArgProvided<bool>,
+    case: Vec<Case>,
+    append_cache: Option<PathBuf>,
+    sentence_boundaries: ArgProvided<bool>,
+    format: Option<Format>,
+    smart_start: ArgProvided<bool>,
+    repl: ArgProvided<bool>,
+    trace: ArgProvided<bool>,
+    speaker: Option<String>,
+    tokenizer: Option<Tokenizer>,
+    estimate_variety: ArgProvided<bool>,
+    skip_cues: Vec<(u64, u64)>,
+    prune: ArgProvided<bool>,
+    json_dict_out: Option<PathBuf>,
+    no_append: ArgProvided<bool>,
+    no_capitalize: ArgProvided<bool>,
+    keep_duplicates: ArgProvided<bool>,
+    recursive: ArgProvided<bool>,
+    glob: Option<String>,
+    stop_at: Vec<String>,
+    longest: ArgProvided<bool>,
+    min_diversity: Option<f64>,
+    no_repeat_window: Option<usize>,
+    cache_file: Option<PathBuf>,
+    output_file: Option<PathBuf>,
+    append_output: ArgProvided<bool>,
+    keep_captions: ArgProvided<bool>,
+    cue_boundaries: ArgProvided<bool>,
+    preview: Option<usize>,
+    pick: Option<usize>,
+    drop_spaces: ArgProvided<bool>,
+    successors: Option<String>,
+    end_word: Option<String>,
+    dict_format: DictFormat
   }
 
 
+  #[cfg_attr(not(feature = "cli"), allow(dead_code))]
   impl UnsafeArguments {
+    #[allow(clippy::too_many_arguments)]
     fn new(
-      subtitle: String,
+      subtitles: Vec<PathBuf>,
       quote_length: ArgProvided<i32>,
       verbosity: ArgProvided<bool>,
-      cache_directory: ArgProvided<String>,
+      cache_directory: ArgProvided<PathBuf>,
       unit: ArgProvided<Unit>,
+      extra_units: Vec<Unit>,
       no_cache: ArgProvided<bool>,
+      separator: ArgProvided<String>,
+      extract_cmd: Option<String>,
+      max_chars: Option<usize>,
+      no_immediate_repeat: ArgProvided<bool>,
+      dump_dialogue: ArgProvided<bool>,
+      keep_punctuation: ArgProvided<bool>,
+      min_length: ArgProvided<i32>,
+      seed: Option<u64>,
+      template: Option<String>,
+      continue_phrase: Option<String>,
+      max_quote_length: Option<i32>,
+      no_start_words: Option<PathBuf>,
+      encoding: Option<&'static Encoding>,
+      must_include: Option<String>,
+      from_time: Option<Duration>,
+      to_time: Option<Duration>,
+      command: Command,
+      with_timecode: ArgProvided<bool>,
+      case: Vec<Case>,
+      append_cache: Option<PathBuf>,
+      sentence_boundaries: ArgProvided<bool>,
+      format: Option<Format>,
+      smart_start: ArgProvided<bool>,
+      repl: ArgProvided<bool>,
+      trace: ArgProvided<bool>,
+      speaker: Option<String>,
+      tokenizer: Option<Tokenizer>,
+      estimate_variety: ArgProvided<bool>,
+      skip_cues: Vec<(u64, u64)>,
+      prune: ArgProvided<bool>,
+      json_dict_out: Option<PathBuf>,
+      no_append: ArgProvided<bool>,
+      no_capitalize: ArgProvided<bool>,
+      keep_duplicates: ArgProvided<bool>,
+      recursive: ArgProvided<bool>,
+      glob: Option<String>,
+      stop_at: Vec<String>,
+      longest: ArgProvided<bool>,
+      min_diversity: Option<f64>,
+      no_repeat_window: Option<usize>,
+      cache_file: Option<PathBuf>,
+      output_file: Option<PathBuf>,
+      append_output: ArgProvided<bool>,
+      keep_captions: ArgProvided<bool>,
+      cue_boundaries: ArgProvided<bool>,
+      preview: Option<usize>,
+      pick: Option<usize>,
+      drop_spaces: ArgProvided<bool>,
+      successors: Option<String>,
+      end_word: Option<String>,
+      dict_format: DictFormat
     ) -> Self {
       Self {
-        subtitle,
+        subtitles,
         quote_length,
         verbosity,
         cache_directory,
         unit,
-        no_cache
+        extra_units,
+        no_cache,
+        separator,
+        extract_cmd,
+        max_chars,
+        no_immediate_repeat,
+        dump_dialogue,
+        keep_punctuation,
+        min_length,
+        seed,
+        template,
+        continue_phrase,
+        max_quote_length,
+        no_start_words,
+        encoding,
+        must_include,
+        from_time,
+        to_time,
+        command,
+        with_timecode,
+        case,
+        append_cache,
+        sentence_boundaries,
+        format,
+        smart_start,
+        repl,
+        trace,
+        speaker,
+        tokenizer,
+        estimate_variety,
+        skip_cues,
+        prune,
+        json_dict_out,
+        no_append,
+        no_capitalize,
+        keep_duplicates,
+        recursive,
+        glob,
+        stop_at,
+        longest,
+        min_diversity,
+        no_repeat_window,
+        cache_file,
+        output_file,
+        append_output,
+        keep_captions,
+        cue_boundaries,
+        preview,
+        pick,
+        drop_spaces,
+        successors,
+        end_word,
+        dict_format
       }
     }
-    fn get_default_quote_length(unit: Option<Unit>) -> i32 {
-      let l_word: i32 = 5;
-      let l_grapheme: i32 = 25;
-      match unit {
-        Some(unit) => {
-          match unit {
-            Unit::Word => l_word,
-            Unit::Grapheme => l_grapheme
-          }
-        },
-        None => {
-          match Self::get_default_unit() {
-            Unit::Word => l_word,
-            Unit::Grapheme => l_grapheme
-          }
-        }
+    pub(crate) fn get_default_quote_length(unit: Option<Unit>) -> i32 {
+      match unit.unwrap_or_else(Self::get_default_unit) {
+        Unit::Word => DEFAULT_WORD_LENGTH,
+        Unit::Grapheme => DEFAULT_GRAPHEME_LENGTH
       }
     }
-    fn get_default_verbosity() -> bool { false }
+    pub(crate) fn get_default_separator(unit: Option<Unit>) -> String {
+      match unit.unwrap_or_else(Self::get_default_unit) {
+        Unit::Word => String::from(" "),
+        Unit::Grapheme => String::new()
+      }
+    }
+    fn get_default_verbosity() -> bool { DEFAULT_VERBOSITY }
     fn get_default_no_cache() -> bool { false }
-    fn get_default_unit() -> Unit { Unit::Word }
-    fn get_default_cache_directory() -> Option<String> {
+    pub(crate) fn get_default_no_immediate_repeat() -> bool { false }
+    pub(crate) fn get_default_min_length() -> i32 { 1 }
+    fn get_default_dump_dialogue() -> bool { false }
+    fn get_default_keep_punctuation() -> bool { false }
+    fn get_default_with_timecode() -> bool { false }
+    fn get_default_sentence_boundaries() -> bool { false }
+    pub(crate) fn get_default_smart_start() -> bool { false }
+    fn get_default_repl() -> bool { false }
+    pub(crate) fn get_default_trace() -> bool { false }
+    fn get_default_estimate_variety() -> bool { false }
+    fn get_default_prune() -> bool { false }
+    pub(crate) fn get_default_no_append() -> bool { false }
+    pub(crate) fn get_default_no_capitalize() -> bool { false }
+    fn get_default_keep_duplicates() -> bool { false }
+    fn get_default_recursive() -> bool { false }
+    pub(crate) fn get_default_longest() -> bool { false }
+    fn get_default_append_output() -> bool { false }
+    fn get_default_keep_captions() -> bool { false }
+    fn get_default_cue_boundaries() -> bool { false }
+    fn get_default_drop_spaces() -> bool { false }
+    pub(crate) fn get_default_unit() -> Unit { DEFAULT_UNIT }
+    fn get_default_dict_format() -> DictFormat { DictFormat::Json }
+    fn get_default_cache_directory() -> Option<PathBuf> {
       let sub_dir = "subquote";
       let default_cache = ".cache";
-      match env::var_os("XDG_CACHE_HOME") {
-        Some(p_os_str) => match p_os_str.into_string() {
-          Ok(p_str) => return Some(format!("{}/{}", p_str, sub_dir)),
-          Err(_) => return None
-        },
-        None => {
-          match env::var_os("HOME") {
-            Some(p_os_str) => match p_os_str.into_string() {
-              Ok(p_str) => return Some(format!("{}/{}/{}", p_str, default_cache, sub_dir)),
-              Err(_) => return None
-            },
-            None => return None
+      // empty or relative values are ignored, as per the XDG base directory spec, HOME is taken as is
+      let from_env = |key: &str| match env::var_os(key) {
+        Some(p_os_str) => {
+          let p_buf = PathBuf::from(p_os_str);
+          if p_buf.is_absolute() {
+            Some(p_buf)
+          } else {
+            None
           }
-        }
+        },
+        None => None
       };
+      if let Some(p_buf) = from_env("SUBQUOTE_CACHE_DIR") {
+        return Some(p_buf);
+      }
+      match from_env("XDG_CACHE_HOME") {
+        Some(p_buf) => Some(p_buf.join(sub_dir)),
+        None => env::var_os("HOME")
+          .filter(|p_os_str| !p_os_str.is_empty())
+          .map(|p_os_str| PathBuf::from(p_os_str).join(default_cache).join(sub_dir))
+      }
     }
-    pub fn validate(&self) -> Result<SafeArguments, String> {
+    pub fn validate(&self) -> Result<SafeArguments, Vec<String>> {
       let mut errors: Vec<String> = Vec::new();
-      if *self.quote_length.get_value() < 1 {
+      if *self.quote_length.get_value() < 0 {
+        errors.push(format!(
+          "quote length must be greater or equal to 0 (got \"{}\")", &self.quote_length)
+        )
+      }
+      if let Some(max_quote_length) = self.max_quote_length {
+        if *self.quote_length.get_value() < 1 || max_quote_length < *self.quote_length.get_value() {
+          errors.push(format!(
+            "quote length range must go from at least 1 to a greater or equal length (got \"{}-{}\")",
+            &self.quote_length,
+            max_quote_length
+          ))
+        }
+      }
+      // with a range, lengths below the minimum are simply never picked
+      let (quote_length, min_length) = (
+        self.max_quote_length.unwrap_or(*self.quote_length.get_value()),
+        *self.min_length.get_value()
+      );
+      if min_length < 1 {
         errors.push(format!(
-          "quote length must be greater or equal to 1 (got \"{}\")", &self.quote_length)
+          "minimum quote length must be greater or equal to 1 (got \"{}\")", &self.min_length)
         )
+      } else if quote_length > 0 && min_length > quote_length {
+        errors.push(format!(
+          "minimum quote length can't be greater than quote length (got \"{}\" and \"{}\")",
+          min_length,
+          quote_length
+        ))
+      }
+      if let Some(0) = self.max_chars {
+        errors.push(String::from("maximum quote characters must be greater or equal to 1 (got \"0\")"))
+      }
+      if let Some(ratio) = self.min_diversity {
+        if ratio <= 0.0 || ratio > 1.0 {
+          errors.push(format!("minimum diversity must be greater than 0 and at most 1 (got \"{}\")", ratio))
+        }
       }
       let (no_cache_provided, no_cache_value) = match &self.no_cache {
         ArgProvided::Yes(no_c) => (true, *no_c),
@@ -184,98 +736,881 @@ pub mod io {
         if let ArgProvided::Yes(_) = &self.cache_directory {
           errors.push(String::from("options \"cache\" and \"no-cache\" cannot be used mutually"));
         }
-      } else {
-        if !path::Path::new(&self.cache_directory.get_value()).is_dir() {
-          match &self.cache_directory {
-            ArgProvided::Yes(_) => {
-              errors.push(format!(
-                "couldn't read specified cache directory (got \"{}\")", &self.cache_directory)
+        if !self.extra_units.is_empty() {
+          errors.push(String::from("multiple units can't be used with option \"no-cache\""));
+        }
+        if self.command == Command::Build {
+          errors.push(String::from("subcommand \"build\" can't be used with option \"no-cache\""));
+        }
+        if self.command == Command::Verify {
+          errors.push(String::from("subcommand \"verify\" can't be used with option \"no-cache\""));
+        }
+        if self.append_cache.is_some() {
+          errors.push(String::from("options \"append-cache\" and \"no-cache\" cannot be used mutually"));
+        }
+      } else if self.cache_file.is_none() && !self.cache_directory.get_value().is_dir() {
+        match &self.cache_directory {
+          ArgProvided::Yes(dir) => {
+            errors.push(format!(
+              "couldn't read specified cache directory (got \"{}\")", dir.display())
+            )
+          },
+          ArgProvided::No(dir) => {
+            // another run may create it in the meantime, which is just as good
+            match fs::create_dir_all(dir) {
+              Ok(_) => {
+                if *self.verbosity.get_value() {
+                  println!("Created default cache directory at {}", dir.display());
+                }
+              },
+              Err(err) if err.kind() == ErrorKind::AlreadyExists && dir.is_dir() => (),
+              Err(err) => errors.push(
+                format!("couldn't create cache directory (got \"{}\"): {}", dir.display(), err)
               )
-            },
-            ArgProvided::No(dir) => {
-              match fs::create_dir(path::Path::new(&dir)) {
-                Ok(_) => {
-                  if *self.verbosity.get_value() {
-                    println!("Created default cache directory at {}", &dir);
-                  }
-                },
-                Err(_) => errors.push(
-                  format!("couldn't create cache directory (got \"{}\")", &self.cache_directory)
-                )
-              }
             }
           }
-        } 
+        }
+      }
+      if let Some(output_file) = &self.output_file {
+        if *self.dump_dialogue.get_value() {
+          errors.push(String::from("options \"output-file\" and \"dump-dialogue\" cannot be used mutually"));
+        }
+        let parent = match output_file.parent() {
+          Some(parent) if !parent.as_os_str().is_empty() => parent,
+          _ => Path::new(".")
+        };
+        if output_file.is_dir() || !parent.is_dir() {
+          errors.push(format!("couldn't write to specified output file (got \"{}\")", output_file.display()))
+        }
+      } else if *self.append_output.get_value() {
+        errors.push(String::from("option \"append-output\" can only be used with option \"output-file\""));
+      }
+      if let Some(preview) = self.preview {
+        if preview == 0 {
+          errors.push(String::from("preview must be greater or equal to 1 (got \"0\")"));
+        }
+        for (name, used) in [
+          ("repl", *self.repl.get_value()),
+          ("dump-dialogue", *self.dump_dialogue.get_value())
+        ].iter() {
+          if *used {
+            errors.push(format!("options \"preview\" and \"{}\" cannot be used mutually", name));
+          }
+        }
       }
-      if !path::Path::new(&self.subtitle).is_file() {
-        errors.push(format!("specified subtitle is not a file (got \"{}\")", &self.subtitle))
+      if let Some(successors) = &self.successors {
+        if successors.trim().is_empty() {
+          errors.push(String::from("successors word can't be empty"));
+        }
+        for (name, used) in [
+          ("repl", *self.repl.get_value()),
+          ("dump-dialogue", *self.dump_dialogue.get_value()),
+          ("preview", self.preview.is_some())
+        ].iter() {
+          if *used {
+            errors.push(format!("options \"successors\" and \"{}\" cannot be used mutually", name));
+          }
+        }
       }
-      if errors.len() > 0 {
-        return Err(errors.join("; "));
+      // a candidate can only be picked again from the same seed
+      match (self.pick, self.preview, self.seed) {
+        (Some(pick), Some(preview), Some(_)) if pick == 0 || pick > preview => {
+          errors.push(format!("pick must be between 1 and {} (got \"{}\")", preview, pick))
+        },
+        (Some(_), None, _) | (Some(_), _, None) => {
+          errors.push(String::from("option \"pick\" can only be used with options \"preview\" and \"seed\""))
+        },
+        _ => ()
       }
-      Ok(SafeArguments::new(
-        self.subtitle.clone(),
-        self.quote_length.get_value().clone(),
-        self.verbosity.get_value().clone(),
-        self.cache_directory.get_value().clone(),
-        (*self.unit.get_value()).clone(),
-        self.no_cache.get_value().clone()
-      ))
+      // the named cache replaces every subtitle's own
+      if let Some(cache_file) = &self.cache_file {
+        for (name, used) in [
+          ("no-cache", no_cache_provided && no_cache_value),
+          ("cache", matches!(self.cache_directory, ArgProvided::Yes(_))),
+          ("append-cache", self.append_cache.is_some())
+        ].iter() {
+          if *used {
+            errors.push(format!("options \"cache-file\" and \"{}\" cannot be used mutually", name));
+          }
+        }
+        if !self.extra_units.is_empty() {
+          errors.push(String::from("multiple units can't be used with option \"cache-file\""));
+        }
+        let parent = match cache_file.parent() {
+          Some(parent) if !parent.as_os_str().is_empty() => parent,
+          _ => Path::new(".")
+        };
+        let read_only = fs::metadata(cache_file).map(|metadata| metadata.permissions().readonly()).unwrap_or(false);
+        if cache_file.is_dir() || !parent.is_dir() || read_only {
+          errors.push(format!("couldn't write to specified cache file (got \"{}\")", cache_file.display()))
+        }
+      }
+      if let Some(template) = &self.template {
+        let placeholder_reg = Regex::new(r"\{([^{}]*)\}").unwrap();
+        for placeholder in placeholder_reg.captures_iter(template) {
+          if !TEMPLATE_PLACEHOLDERS.contains(&&placeholder[1]) {
+            errors.push(format!(
+              "unknown template placeholder \"{}\" (expected one of {})",
+              &placeholder[0],
+              TEMPLATE_PLACEHOLDERS.iter().map(|name| format!("{{{}}}", name)).collect::<Vec<String>>().join(", ")
+            ))
+          }
+        }
+      }
+      if let Some(phrase) = &self.continue_phrase {
+        if phrase.trim().is_empty() {
+          errors.push(String::from("phrase to continue can't be empty"))
+        }
+        if self.must_include.is_some() {
+          errors.push(String::from("options \"continue\" and \"must-include\" cannot be used mutually"))
+        }
+      }
+      if let Some(word) = &self.end_word {
+        if word.is_empty() {
+          errors.push(String::from("word to end on can't be empty"))
+        }
+        for (name, used) in [
+          ("continue", self.continue_phrase.is_some()),
+          ("must-include", self.must_include.is_some())
+        ].iter() {
+          if *used {
+            errors.push(format!("options \"end\" and \"{}\" cannot be used mutually", name));
+          }
+        }
+      }
+      if let (Some(from_time), Some(to_time)) = (self.from_time, self.to_time) {
+        if from_time > to_time {
+          errors.push(format!(
+            "start time can't be after end time (got \"{}\" and \"{}\")",
+            format_time(from_time),
+            format_time(to_time)
+          ))
+        }
+      }
+      if self.command == Command::Verify && !self.subtitles.is_empty() {
+        errors.push(String::from("subcommand \"verify\" doesn't take subtitle files"));
+      }
+      // the REPL prints its quotes as they are, generated from a start or a phrase only
+      if *self.repl.get_value() {
+        for (name, used) in [
+          ("dump-dialogue", *self.dump_dialogue.get_value()),
+          ("template", self.template.is_some()),
+          ("with-timecode", *self.with_timecode.get_value()),
+          ("must-include", self.must_include.is_some()),
+          ("end", self.end_word.is_some())
+        ].iter() {
+          if *used {
+            errors.push(format!("options \"repl\" and \"{}\" cannot be used mutually", name));
+          }
+        }
+      }
+      match self.no_repeat_window {
+        Some(0) => errors.push(String::from("no repeat window must be greater or equal to 1 (got \"0\")")),
+        Some(_) if !*self.repl.get_value() => {
+          errors.push(String::from("option \"no-repeat-window\" can only be used with option \"repl\""))
+        },
+        _ => ()
+      }
+      // these start from any unit, or go on past the length the dictionary was pruned for
+      if *self.prune.get_value() {
+        for (name, used) in [
+          ("continue", self.continue_phrase.is_some()),
+          ("must-include", self.must_include.is_some()),
+          ("end", self.end_word.is_some()),
+          ("repl", *self.repl.get_value())
+        ].iter() {
+          if *used {
+            errors.push(format!("options \"prune\" and \"{}\" cannot be used mutually", name));
+          }
+        }
+      }
+      if *self.longest.get_value() {
+        for (name, used) in [
+          ("length", matches!(self.quote_length, ArgProvided::Yes(_))),
+          ("continue", self.continue_phrase.is_some()),
+          ("must-include", self.must_include.is_some()),
+          ("end", self.end_word.is_some())
+        ].iter() {
+          if *used {
+            errors.push(format!("options \"longest\" and \"{}\" cannot be used mutually", name));
+          }
+        }
+      }
+      if self.case.len() > 1 {
+        errors.push(String::from("options \"lowercase\", \"uppercase\" and \"title-case\" cannot be used mutually"))
+      }
+      if let (Some(_), Unit::Grapheme) = (&self.tokenizer, self.unit.get_value()) {
+        errors.push(String::from("tokenizer only applies to the word unit"))
+      }
+      let grapheme = *self.unit.get_value() == Unit::Grapheme || self.extra_units.contains(&Unit::Grapheme);
+      if *self.drop_spaces.get_value() && !grapheme {
+        errors.push(String::from("option \"drop-spaces\" only applies to the char unit"))
+      }
+      if self.stop_at.iter().any(String::is_empty) {
+        errors.push(String::from("stop word can't be empty"))
+      }
+      if let Some("") = self.speaker.as_deref() {
+        errors.push(String::from("speaker can't be empty"))
+      }
+      if let Some(word) = &self.must_include {
+        if word.trim().is_empty() {
+          errors.push(String::from("word to include can't be empty"))
+        }
+      }
+      let mut no_start_words: Vec<String> = Vec::new();
+      if let Some(words_file) = &self.no_start_words {
+        match fs::read_to_string(words_file) {
+          Ok(words) => no_start_words = words.split_whitespace().map(str::to_lowercase).collect(),
+          Err(_) => errors.push(format!("couldn't read start words file (got \"{}\")", words_file.display()))
+        }
+      }
+      let file_reg = match &self.glob {
+        Some(glob) => glob_regex(glob),
+        None => Regex::new(r"(?i)\.(srt|vtt|ass)(\.gz)?$").unwrap()
+      };
+      let mut subtitles: Vec<PathBuf> = Vec::new();
+      for subtitle in self.subtitles.iter() {
+        if subtitle.is_dir() {
+          let mut found = subtitles_in(subtitle, &file_reg, *self.recursive.get_value(), &mut HashSet::new());
+          if found.is_empty() {
+            errors.push(format!(
+              "found no subtitle matching \"{}\" in directory \"{}\"",
+              self.glob.as_deref().unwrap_or("*.srt|*.vtt|*.ass"),
+              subtitle.display()
+            ))
+          }
+          subtitles.append(&mut found);
+        } else if subtitle.is_file() {
+          subtitles.push(subtitle.clone());
+        } else {
+          errors.push(format!("specified subtitle is not a file (got \"{}\")", subtitle.display()))
+        }
+      }
+      if !errors.is_empty() {
+        return Err(errors);
+      }
+      Ok(SafeArguments {
+        subtitles,
+        quote_length: *self.quote_length.get_value(),
+        verbosity: *self.verbosity.get_value(),
+        cache_directory: self.cache_directory.get_value().clone(),
+        unit: (*self.unit.get_value()).clone(),
+        extra_units: self.extra_units.clone(),
+        no_cache: *self.no_cache.get_value(),
+        separator: self.separator.get_value().clone(),
+        extract_cmd: self.extract_cmd.clone(),
+        max_chars: self.max_chars,
+        no_immediate_repeat: *self.no_immediate_repeat.get_value(),
+        dump_dialogue: *self.dump_dialogue.get_value(),
+        keep_punctuation: *self.keep_punctuation.get_value(),
+        min_length: *self.min_length.get_value(),
+        seed: self.seed,
+        template: self.template.clone(),
+        continue_phrase: self.continue_phrase.clone(),
+        max_quote_length: self.max_quote_length,
+        no_start_words,
+        encoding: self.encoding,
+        must_include: self.must_include.clone(),
+        from_time: self.from_time,
+        to_time: self.to_time,
+        command: self.command,
+        with_timecode: *self.with_timecode.get_value(),
+        case: self.case.first().cloned(),
+        append_cache: self.append_cache.clone(),
+        sentence_boundaries: *self.sentence_boundaries.get_value(),
+        format: self.format,
+        smart_start: *self.smart_start.get_value(),
+        repl: *self.repl.get_value(),
+        trace: *self.trace.get_value(),
+        speaker: self.speaker.clone(),
+        tokenizer: self.tokenizer.clone().unwrap_or_default(),
+        estimate_variety: *self.estimate_variety.get_value(),
+        skip_cues: self.skip_cues.clone(),
+        prune: *self.prune.get_value(),
+        json_dict_out: self.json_dict_out.clone(),
+        no_append: *self.no_append.get_value(),
+        no_capitalize: *self.no_capitalize.get_value(),
+        keep_duplicates: *self.keep_duplicates.get_value(),
+        recursive: *self.recursive.get_value(),
+        glob: self.glob.clone(),
+        stop_at: self.stop_at.iter().map(|word| word.to_lowercase()).collect(),
+        longest: *self.longest.get_value(),
+        min_diversity: self.min_diversity,
+        no_repeat_window: self.no_repeat_window,
+        cache_file: self.cache_file.clone(),
+        output_file: self.output_file.clone(),
+        append_output: *self.append_output.get_value(),
+        keep_captions: *self.keep_captions.get_value(),
+        cue_boundaries: *self.cue_boundaries.get_value(),
+        preview: self.preview,
+        pick: self.pick,
+        drop_spaces: *self.drop_spaces.get_value(),
+        successors: self.successors.clone(),
+        end_word: self.end_word.clone(),
+        dict_format: self.dict_format
+      })
+    }
+  }
+
+  #[cfg(feature = "cli")]
+  fn parse_time(time: &str) -> Option<Duration> {
+    let time_reg = Regex::new(r"^(\d+):([0-5]\d):([0-5]\d)$").unwrap();
+    let caps = time_reg.captures(time.trim())?;
+    let (hours, minutes, seconds): (u64, u64, u64) = (caps[1].parse().ok()?, caps[2].parse().ok()?, caps[3].parse().ok()?);
+    Some(Duration::from_secs(hours.checked_mul(3600)?.checked_add(minutes * 60 + seconds)?))
+  }
+
+  // comma separated cue numbers or inclusive ranges of them, like "1,5,10-12"
+  #[cfg(feature = "cli")]
+  fn parse_cue_ranges(ranges: &str) -> Option<Vec<(u64, u64)>> {
+    ranges.split(',')
+      .map(|range| {
+        let mut bounds = range.trim().splitn(2, '-').map(|bound| bound.trim().parse::<u64>().ok());
+        let first = bounds.next()??;
+        let last = bounds.next().unwrap_or(Some(first))?;
+        Some((first, last)).filter(|(first, last)| first <= last)
+      })
+      .collect()
+  }
+
+  // glob patterns match whole file names
+  fn glob_regex(glob: &str) -> Regex {
+    let pattern: String = glob.chars()
+      .map(|c| match c {
+        '*' => String::from(".*"),
+        '?' => String::from("."),
+        c => regex::escape(&c.to_string())
+      })
+      .collect();
+    Regex::new(&format!("^{}$", pattern)).unwrap()
+  }
+
+  // sorted so that a directory always yields its subtitles in the same order; symbolic links are
+  // followed, but a directory already `visited` (by its canonical path) isn't read again, so that
+  // a link to a parent doesn't loop forever
+  fn subtitles_in(dir: &Path, file_reg: &Regex, recursive: bool, visited: &mut HashSet<PathBuf>) -> Vec<PathBuf> {
+    if !fs::canonicalize(dir).is_ok_and(|canonical| visited.insert(canonical)) {
+      return Vec::new();
+    }
+    let mut entries: Vec<PathBuf> = match fs::read_dir(dir) {
+      Ok(entries) => entries.filter_map(|entry| entry.ok().map(|entry| entry.path())).collect(),
+      Err(_) => return Vec::new()
+    };
+    entries.sort();
+    let mut subtitles: Vec<PathBuf> = Vec::new();
+    for entry in entries {
+      if entry.is_dir() {
+        if recursive {
+          subtitles.append(&mut subtitles_in(&entry, file_reg, recursive, visited));
+        }
+      } else if entry.file_name().and_then(|name| name.to_str()).is_some_and(|name| file_reg.is_match(name)) {
+        subtitles.push(entry);
+      }
+    }
+    subtitles
+  }
+
+  pub fn format_time(time: Duration) -> String {
+    let seconds = time.as_secs();
+    format!("{:02}:{:02}:{:02}", seconds / 3600, seconds % 3600 / 60, seconds % 60)
+  }
+
+  // "@FILE" arguments are replaced by the whitespace separated arguments FILE contains, nested paths
+  // being relative to the file referencing them
+  #[cfg(feature = "cli")]
+  fn expand_response_files(args: &[String], base: &Path, open: &mut Vec<PathBuf>) -> Result<Vec<String>, String> {
+    let mut expanded: Vec<String> = Vec::new();
+    for arg in args.iter() {
+      let file = match arg.strip_prefix('@') {
+        Some(file) if !file.is_empty() => base.join(file),
+        _ => {
+          expanded.push(arg.clone());
+          continue;
+        }
+      };
+      let content = match fs::read_to_string(&file) {
+        Ok(content) => content,
+        Err(err) => return Err(format!("couldn't read response file \"{}\": {}", file.display(), err))
+      };
+      let canonical = file.canonicalize().unwrap_or_else(|_| file.clone());
+      if open.contains(&canonical) {
+        return Err(format!("response file \"{}\" includes itself", file.display()));
+      }
+      let nested: Vec<String> = content.split_whitespace().map(String::from).collect();
+      open.push(canonical);
+      expanded.extend(expand_response_files(&nested, file.parent().unwrap_or(base), open)?);
+      open.pop();
     }
+    Ok(expanded)
+  }
+
+  #[cfg(feature = "cli")]
+  fn usage(program: &str, opts: &Options) -> String {
+    let brief = format!(
+      "Usage: {0} [gen] FILE.srt... [options]\n       {0} build FILE.srt... [options]\n       {0} stats FILE.srt... [options]\n       \
+      {0} verify [options]\n\n\
+      Subcommands:\n    gen     Generate a quote (default)\n    build   Build the caches without generating\n    \
+      stats   Report each subtitle's dictionary stats\n    \
+      verify  Report the caches of the cache directory that can't be used or are outdated\n\n\
+      An @FILE argument is replaced by the whitespace separated arguments FILE contains.\n\
+      A directory is replaced by the subtitles it holds (see --recursive and --glob).\n\
+      A subtitle named like a subcommand is given after \"gen\" or \"--\".\n\n\
+      Exit status:\n    0       Success\n    2       Invalid arguments\n    \
+      3       Subtitles or caches couldn't be read or written\n    \
+      4       The subtitles can't produce the requested quote",
+      program
+    );
+    opts.usage(&brief)
   }
 
-  fn print_usage(program: &str, opts: Options) {
-    let brief = format!("Usage: {} FILE.srt [options]", program);
-    println!("{}", opts.usage(&brief));
+  // built from the option names so that the examples follow their renaming
+  #[cfg(feature = "cli")]
+  fn usage_examples(program: &str, unit: &str, length: &str, recursive: &str, glob: &str) -> String {
+    format!(
+      "Examples:\n    {0} build show.srt --{1} word,char\n        \
+      Build the word and char caches of show.srt ahead of time\n    \
+      for i in 1 2 3 4 5; do {0} show.srt --{2} 4-10; done\n        \
+      Generate 5 quotes of 4 to 10 words\n    \
+      {0} show.srt --{1} char --{2} 30\n        \
+      Generate a quote of 30 characters\n    \
+      {0} s01e01.srt s01e02.srt --{2} 12\n        \
+      Generate a quote from both episodes\n    \
+      {0} season1/ --{3} --{4} \"*.vtt\"\n        \
+      Generate a quote from the WebVTT subtitles of a directory and its subdirectories",
+      program, unit, length, recursive, glob
+    )
   }
 
-  pub fn parse_args(args: &[String]) -> Result<UnsafeArguments, ParseOutcome> {
-    let program = &args[0];
+  // prints the usage on help, see `try_parse_args` to handle it instead
+  #[cfg(feature = "cli")]
+  pub fn parse_args(raw_args: &[String]) -> Result<UnsafeArguments, ParseOutcome> {
+    let parsed = try_parse_args(raw_args);
+    if let Err(ParseOutcome::Help(usage)) = &parsed {
+      println!("{}", usage);
+    }
+    parsed
+  }
+
+  #[cfg(feature = "cli")]
+  pub fn try_parse_args(raw_args: &[String]) -> Result<UnsafeArguments, ParseOutcome> {
+    let program = match raw_args.first() {
+      Some(program) => program,
+      None => return Err(ParseOutcome::Error(String::from("missing program name in arguments")))
+    };
     let def_quote_length = UnsafeArguments::get_default_quote_length(None);
     let def_verbosity = UnsafeArguments::get_default_verbosity();
     let def_unit = UnsafeArguments::get_default_unit();
     let def_cache_directory = UnsafeArguments::get_default_cache_directory();
     let def_no_cache = UnsafeArguments::get_default_no_cache();
+    let def_no_immediate_repeat = UnsafeArguments::get_default_no_immediate_repeat();
+    let def_dump_dialogue = UnsafeArguments::get_default_dump_dialogue();
+    let def_keep_punctuation = UnsafeArguments::get_default_keep_punctuation();
+    let def_min_length = UnsafeArguments::get_default_min_length();
+    let def_with_timecode = UnsafeArguments::get_default_with_timecode();
+    let def_sentence_boundaries = UnsafeArguments::get_default_sentence_boundaries();
+    let def_smart_start = UnsafeArguments::get_default_smart_start();
+    let def_repl = UnsafeArguments::get_default_repl();
+    let def_trace = UnsafeArguments::get_default_trace();
+    let def_estimate_variety = UnsafeArguments::get_default_estimate_variety();
+    let def_prune = UnsafeArguments::get_default_prune();
+    let def_no_append = UnsafeArguments::get_default_no_append();
+    let def_no_capitalize = UnsafeArguments::get_default_no_capitalize();
+    let def_keep_duplicates = UnsafeArguments::get_default_keep_duplicates();
+    let def_recursive = UnsafeArguments::get_default_recursive();
+    let def_longest = UnsafeArguments::get_default_longest();
+    let def_append_output = UnsafeArguments::get_default_append_output();
+    let def_keep_captions = UnsafeArguments::get_default_keep_captions();
+    let def_cue_boundaries = UnsafeArguments::get_default_cue_boundaries();
+    let def_drop_spaces = UnsafeArguments::get_default_drop_spaces();
 
-    let desc_quote_length = format!("Maximum quote length (default: {})", def_quote_length);
+    let desc_quote_length = format!(
+      "Maximum quote length, 0 or \"max\" to go on until the chain ends, or a range like 3-8 to pick a \
+      random length within it (default: {})",
+      def_quote_length
+    );
     let desc_verbosity = format!("Be verbose (default: {})", def_verbosity);
     let desc_no_cache = format!("Prevent cache usage (default: {})", def_no_cache);
-    let desc_unit = format!("Unit used to build the quote: \"word\" or \"char\" (default: {})", def_unit);
+    let desc_unit = format!(
+      "Unit used to build the quote: \"word\" or \"char\", or a comma separated list of units to cache, \
+      the first one being used to build the quote (default: {})",
+      def_unit
+    );
+    let desc_separator = String::from(
+      "String used to join units in the quote (default: \" \" for words, \"\" for chars)"
+    );
+    let desc_extract_cmd = String::from(
+      "Shell command printing SRT subtitles from FILE on its standard output, {} being replaced by FILE \
+      (e.g. \"ffmpeg -loglevel error -i {} -map 0:s:0 -f srt -\" to extract a video's first subtitle track)"
+    );
+    let desc_max_chars = String::from("Maximum number of characters of the quote, trimming the last units");
+    let desc_no_repeat_window = String::from(
+      "Generate a quote again, a few times at most, when it's the same as one of the last N ones of the REPL"
+    );
+    let desc_min_diversity = String::from(
+      "Minimum ratio of distinct units (ignoring case) to units in the quote, between 0 and 1; \
+      quotes repeating themselves more than that are generated again a few times"
+    );
+    let desc_no_immediate_repeat = format!(
+      "Avoid repeating the last two units when another successor is available (default: {})",
+      def_no_immediate_repeat
+    );
+    let desc_dump_dialogue = format!(
+      "Print the subtitle's cleaned dialogue lines instead of a quote (default: {})", def_dump_dialogue
+    );
+    let desc_keep_punctuation = format!(
+      "Keep punctuation marks as units of their own (default: {})", def_keep_punctuation
+    );
+    let desc_min_length = format!("Minimum quote length (default: {})", def_min_length);
+    let desc_seed = String::from("Seed making the quote generation reproducible");
+    let desc_template = String::from(
+      "Template wrapping the quote, with {quote}, {source} (subtitle file names), {length} (quote length), \
+      {unit} and {timecode} (start time of the quote's first unit, if known) being replaced \
+      (e.g. \"{quote} - {source}\")"
+    );
+    let desc_continue = String::from(
+      "Continue PHRASE from its last unit found in the subtitles instead of starting a new quote, \
+      the length counting the added units"
+    );
+    let desc_no_start_words = String::from(
+      "File listing words (separated by whitespace) that shouldn't start the quote, unless no other word can"
+    );
+    let desc_format = String::from(
+      "Format of the subtitles: \"srt\", \"vtt\", \"ass\", \"text\" (one cue per line), or \"auto\" to detect it \
+      from the file extension then the content (default: auto)"
+    );
+    let desc_dict_format = format!(
+      "Format of the cached dictionaries: \"json\", or \"binary\" which is faster to load but can't be read \
+      as text; binary caches are named after the JSON ones with a \".bin\" extension (default: {})",
+      UnsafeArguments::get_default_dict_format()
+    );
+    let desc_encoding = String::from(
+      "Encoding of the subtitles, e.g. \"latin1\" or \"windows-1252\" (default: utf8)"
+    );
+    let desc_must_include = String::from(
+      "Build the quote around WORD, extending it in both directions"
+    );
+    let desc_end_word = String::from(
+      "End the quote on WORD, walking back through the units preceding it up to the length or the start \
+      of a sentence"
+    );
+    let desc_cache_file = String::from(
+      "Cache the merged dictionary of all the subtitles in FILE instead of caching each subtitle \
+      in the cache directory, building it when it's missing or unreadable"
+    );
+    let desc_append_cache = String::from(
+      "Merge the subtitles' dictionary into the corpus cache FILE when building, creating it if needed, to \
+      generate from with --cache-file FILE (the corpus doesn't keep track of its sources, appending a subtitle \
+      twice doubles its weight)"
+    );
+    let desc_json_dict_out = String::from(
+      "Also export the subtitles' dictionary to FILE as a JSON object mapping each key to the number of times \
+      each of its successors follows it, sorted and pretty-printed"
+    );
+    let desc_glob = String::from(
+      "File names of the subtitles to use in the directories given as FILE, where \"*\" matches any characters \
+      and \"?\" a single one (default: SRT, WebVTT and ASS files, possibly gzipped)"
+    );
+    let desc_stop_at = String::from(
+      "End the quote as soon as WORD is picked, whatever the requested length (can be repeated, ignoring case); \
+      quotes stopped short of the minimum length are generated again a few times"
+    );
+    let desc_tokenizer = String::from(
+      "How lines are split into words: \"whitespace\", \"unicode-words\" (Unicode word boundaries, for languages \
+      written without spaces) or \"regex:PATTERN\" to split at the matches of PATTERN (default: whitespace)"
+    );
+    let desc_speaker = String::from(
+      "Only use the lines of speaker NAME, labelled like \"[NAME]\", \"(NAME)\" or \"NAME:\" at the start of a cue's \
+      line, by a WebVTT voice span or by the Name field of ASS events"
+    );
+    let desc_skip_cues = String::from(
+      "Leave out the cues numbered as listed, like \"1,5,10-12\"; cues without a number are counted from 1"
+    );
+    let desc_from_time = String::from("Only use the cues starting at or after this time");
+    let desc_to_time = String::from("Only use the cues starting at or before this time");
+    let desc_with_timecode = format!(
+      "Print the start time of the cue the quote's first unit comes from before the quote (default: {})",
+      def_with_timecode
+    );
+    let desc_sentence_boundaries = format!(
+      "Split the dialogue into sentences at \".\", \"?\" and \"!\", never linking two sentences, and start \
+      quotes at the beginning of a sentence rather than at any capitalized unit (default: {})",
+      def_sentence_boundaries
+    );
+    let desc_smart_start = format!(
+      "Pick the starting unit with a probability proportional to its number of successors rather than \
+      uniformly (default: {})",
+      def_smart_start
+    );
+    let desc_repl = format!(
+      "Load the subtitles once then read commands from the standard input: an empty line generates a quote, \
+      \":start WORD\" one starting with WORD, \":len N\" sets the length and \":quit\" exits (default: {})",
+      def_repl
+    );
+    let desc_trace = format!(
+      "Print each step of the chain on the standard error, as \"unit -> successor (N candidates)\" (default: {})",
+      def_trace
+    );
+    let desc_estimate_variety = format!(
+      "With \"stats\", also estimate how many distinct quotes could be generated from the number of starting units \
+      and the geometric mean of the successors per unit (default: {})",
+      def_estimate_variety
+    );
+    let desc_prune = format!(
+      "Drop the keys no quote of the requested length can reach from a starting unit, making caches smaller \
+      (default: {})",
+      def_prune
+    );
+    let desc_no_append = format!(
+      "Don't end the quote with a period when it lacks closing punctuation (default: {})",
+      def_no_append
+    );
+    let desc_no_capitalize = format!(
+      "Don't capitalize the first letter of the quote when it starts in lowercase (default: {})",
+      def_no_capitalize
+    );
+    let desc_keep_duplicates = format!(
+      "Keep the lines repeated as is by the following cue, which are otherwise only counted once (default: {})",
+      def_keep_duplicates
+    );
+    let desc_recursive = format!(
+      "Also look for subtitles in the subdirectories of the directories given as FILE (default: {})",
+      def_recursive
+    );
+    let desc_longest = format!(
+      "Instead of a given length, search for the longest quote from the start that never repeats a pair of \
+      units (the search is bounded, so on large subtitles it's only the longest found) (default: {})",
+      def_longest
+    );
+    let desc_preview = String::from(
+      "Print N numbered candidate quotes to pick from, candidate K being the one of seed SEED + K - 1"
+    );
+    let desc_pick = String::from(
+      "Print only candidate K of the preview, given the same seed (see --preview and --seed)"
+    );
+    let desc_successors = String::from(
+      "Print the units following WORD in the dictionary, along with how many times each does, instead of a quote"
+    );
+    let desc_output_file = String::from(
+      "Write the quote to FILE instead of the standard output, one quote per line with \"repl\""
+    );
+    let desc_append_output = format!(
+      "Append to the output file rather than overwriting it (default: {})", def_append_output
+    );
+    let desc_keep_captions = format!(
+      "Keep the captions of sounds and music, like \"[door slams]\", \"(sighs)\" or \"♪ la la ♪\", which are \
+      otherwise removed from the dialogue (default: {})",
+      def_keep_captions
+    );
+    let desc_cue_boundaries = format!(
+      "Start quotes at the beginning of a cue's dialogue rather than at any capitalized unit, a cue being \
+      usually one thought; can be combined with \"punctuation-as-boundary\" (default: {})",
+      def_cue_boundaries
+    );
+    let desc_drop_spaces = format!(
+      "Leave the spaces out of the char chain, which otherwise links each word to the next through a \
+      single space (runs of whitespace being collapsed), so that quotes run the letters together (default: {})",
+      def_drop_spaces
+    );
+    let desc_lowercase = String::from("Print the quote in lowercase");
+    let desc_uppercase = String::from("Print the quote in uppercase");
+    let desc_title_case = String::from("Print the quote with each word capitalized");
     let desc_help = String::from("Print this help menu");
-    let desc_cache_base = String::from("Specify where to save processed subtitles");
+    let desc_help_format = String::from(
+      "Print the help menu as \"brief\" or with \"examples\" of invocations (default: brief)"
+    );
+    let desc_version = String::from("Print version information");
+    let desc_cache_base = String::from(
+      "Specify where to save processed subtitles, overriding the SUBQUOTE_CACHE_DIR environment variable \
+      and the XDG cache directory"
+    );
     let desc_cache_directory = match def_cache_directory.clone() {
-      Some(p_str) => {
-        format!("{} (default {})", desc_cache_base, p_str)
+      Some(p_buf) => {
+        format!("{} (default {})", desc_cache_base, p_buf.display())
       },
       None => desc_cache_base
     };
 
+    // options are always looked up by their long name, which every one of them has
     let mut opts = Options::new();
     let opt_l = ("l", "length", &desc_quote_length, &def_quote_length.to_string());
-    let opt_u = ("u", "unit", &desc_unit, "word|char");
+    let opt_u = ("u", "unit", &desc_unit, "word|char[,...]");
     let opt_c = ("", "cache", &desc_cache_directory, "~/path/to/cache");
     let opt_n = ("", "no-cache", &desc_no_cache);
-    let opt_v = ("v", "", &desc_verbosity);
+    let opt_s = ("", "separator", &desc_separator, "STR");
+    let opt_x = ("", "extract-cmd", &desc_extract_cmd, "CMD");
+    let opt_m = ("", "max-chars", &desc_max_chars, "N");
+    let opt_div = ("", "min-diversity", &desc_min_diversity, "RATIO");
+    let opt_nrw = ("", "no-repeat-window", &desc_no_repeat_window, "N");
+    let opt_r = ("", "no-immediate-repeat", &desc_no_immediate_repeat);
+    let opt_d = ("", "dump-dialogue", &desc_dump_dialogue);
+    let opt_p = ("", "keep-punctuation", &desc_keep_punctuation);
+    let opt_min = ("", "min-length", &desc_min_length, &def_min_length.to_string());
+    let opt_seed = ("", "seed", &desc_seed, "N");
+    let opt_t = ("", "template", &desc_template, "STR");
+    let opt_cont = ("", "continue", &desc_continue, "PHRASE");
+    let opt_nsw = ("", "no-start-words", &desc_no_start_words, "FILE");
+    let opt_enc = ("", "encoding", &desc_encoding, "NAME");
+    let opt_fmt = ("", "format", &desc_format, "FORMAT");
+    let opt_df = ("", "dict-format", &desc_dict_format, "FORMAT");
+    let opt_inc = ("", "must-include", &desc_must_include, "WORD");
+    let opt_end = ("", "end", &desc_end_word, "WORD");
+    let opt_app = ("", "append-cache", &desc_append_cache, "FILE");
+    let opt_cf = ("", "cache-file", &desc_cache_file, "FILE");
+    let opt_pv = ("", "preview", &desc_preview, "N");
+    let opt_pk = ("", "pick", &desc_pick, "K");
+    let opt_succ = ("", "successors", &desc_successors, "WORD");
+    let opt_json = ("", "json-dict-out", &desc_json_dict_out, "FILE");
+    let opt_glob = ("", "glob", &desc_glob, "PATTERN");
+    let opt_stop = ("", "stop-at", &desc_stop_at, "WORD");
+    let opt_tok = ("", "tokenizer", &desc_tokenizer, "TOKENIZER");
+    let opt_spk = ("", "speaker", &desc_speaker, "NAME");
+    let opt_skip = ("", "skip-cues", &desc_skip_cues, "CUES");
+    let opt_from = ("", "from-time", &desc_from_time, "HH:MM:SS");
+    let opt_to = ("", "to-time", &desc_to_time, "HH:MM:SS");
+    let opt_tc = ("", "with-timecode", &desc_with_timecode);
+    let opt_sb = ("", "punctuation-as-boundary", &desc_sentence_boundaries);
+    let opt_ss = ("", "smart-start", &desc_smart_start);
+    let opt_repl = ("", "repl", &desc_repl);
+    let opt_trace = ("", "trace", &desc_trace);
+    let opt_var = ("", "estimate-variety", &desc_estimate_variety);
+    let opt_prune = ("", "prune", &desc_prune);
+    let opt_na = ("", "no-append", &desc_no_append);
+    let opt_nc = ("", "no-capitalize", &desc_no_capitalize);
+    let opt_kd = ("", "keep-duplicates", &desc_keep_duplicates);
+    let opt_rec = ("r", "recursive", &desc_recursive);
+    let opt_long = ("", "longest", &desc_longest);
+    let opt_out = ("", "output-file", &desc_output_file, "FILE");
+    let opt_ao = ("", "append-output", &desc_append_output);
+    let opt_kc = ("", "keep-captions", &desc_keep_captions);
+    let opt_cb = ("", "cue-boundaries", &desc_cue_boundaries);
+    let opt_ds = ("", "drop-spaces", &desc_drop_spaces);
+    let opt_lc = ("", "lowercase", &desc_lowercase);
+    let opt_uc = ("", "uppercase", &desc_uppercase);
+    let opt_tic = ("", "title-case", &desc_title_case);
+    let opt_v = ("v", "verbose", &desc_verbosity);
     let opt_h = ("h", "help", &desc_help);
+    let opt_hf = ("", "help-format", &desc_help_format, "FORMAT");
+    let opt_ver = ("V", "version", &desc_version);
     opts.optopt(opt_l.0, opt_l.1, opt_l.2, opt_l.3);
     opts.optopt(opt_u.0, opt_u.1, opt_u.2, opt_u.3);
     opts.optopt(opt_c.0, opt_c.1, opt_c.2, opt_c.3);
+    opts.optopt(opt_s.0, opt_s.1, opt_s.2, opt_s.3);
+    opts.optopt(opt_x.0, opt_x.1, opt_x.2, opt_x.3);
+    opts.optopt(opt_m.0, opt_m.1, opt_m.2, opt_m.3);
+    opts.optopt(opt_div.0, opt_div.1, opt_div.2, opt_div.3);
+    opts.optopt(opt_nrw.0, opt_nrw.1, opt_nrw.2, opt_nrw.3);
+    opts.optopt(opt_min.0, opt_min.1, opt_min.2, opt_min.3);
+    opts.optopt(opt_seed.0, opt_seed.1, opt_seed.2, opt_seed.3);
+    opts.optopt(opt_t.0, opt_t.1, opt_t.2, opt_t.3);
+    opts.optopt(opt_cont.0, opt_cont.1, opt_cont.2, opt_cont.3);
+    opts.optopt(opt_nsw.0, opt_nsw.1, opt_nsw.2, opt_nsw.3);
+    opts.optopt(opt_enc.0, opt_enc.1, opt_enc.2, opt_enc.3);
+    opts.optopt(opt_fmt.0, opt_fmt.1, opt_fmt.2, opt_fmt.3);
+    opts.optopt(opt_df.0, opt_df.1, opt_df.2, opt_df.3);
+    opts.optopt(opt_inc.0, opt_inc.1, opt_inc.2, opt_inc.3);
+    opts.optopt(opt_end.0, opt_end.1, opt_end.2, opt_end.3);
+    opts.optopt(opt_app.0, opt_app.1, opt_app.2, opt_app.3);
+    opts.optopt(opt_cf.0, opt_cf.1, opt_cf.2, opt_cf.3);
+    opts.optopt(opt_out.0, opt_out.1, opt_out.2, opt_out.3);
+    opts.optopt(opt_pv.0, opt_pv.1, opt_pv.2, opt_pv.3);
+    opts.optopt(opt_pk.0, opt_pk.1, opt_pk.2, opt_pk.3);
+    opts.optopt(opt_succ.0, opt_succ.1, opt_succ.2, opt_succ.3);
+    opts.optopt(opt_json.0, opt_json.1, opt_json.2, opt_json.3);
+    opts.optopt(opt_glob.0, opt_glob.1, opt_glob.2, opt_glob.3);
+    opts.optmulti(opt_stop.0, opt_stop.1, opt_stop.2, opt_stop.3);
+    opts.optopt(opt_tok.0, opt_tok.1, opt_tok.2, opt_tok.3);
+    opts.optopt(opt_spk.0, opt_spk.1, opt_spk.2, opt_spk.3);
+    opts.optopt(opt_skip.0, opt_skip.1, opt_skip.2, opt_skip.3);
+    opts.optopt(opt_from.0, opt_from.1, opt_from.2, opt_from.3);
+    opts.optopt(opt_to.0, opt_to.1, opt_to.2, opt_to.3);
+    opts.optopt(opt_hf.0, opt_hf.1, opt_hf.2, opt_hf.3);
     opts.optflag(opt_v.0, opt_v.1, opt_v.2);
     opts.optflag(opt_n.0, opt_n.1, opt_n.2);
+    opts.optflag(opt_r.0, opt_r.1, opt_r.2);
+    opts.optflag(opt_d.0, opt_d.1, opt_d.2);
+    opts.optflag(opt_p.0, opt_p.1, opt_p.2);
+    opts.optflag(opt_tc.0, opt_tc.1, opt_tc.2);
+    opts.optflag(opt_sb.0, opt_sb.1, opt_sb.2);
+    opts.optflag(opt_ss.0, opt_ss.1, opt_ss.2);
+    opts.optflag(opt_repl.0, opt_repl.1, opt_repl.2);
+    opts.optflag(opt_trace.0, opt_trace.1, opt_trace.2);
+    opts.optflag(opt_var.0, opt_var.1, opt_var.2);
+    opts.optflag(opt_prune.0, opt_prune.1, opt_prune.2);
+    opts.optflag(opt_na.0, opt_na.1, opt_na.2);
+    opts.optflag(opt_nc.0, opt_nc.1, opt_nc.2);
+    opts.optflag(opt_kd.0, opt_kd.1, opt_kd.2);
+    opts.optflag(opt_rec.0, opt_rec.1, opt_rec.2);
+    opts.optflag(opt_long.0, opt_long.1, opt_long.2);
+    opts.optflag(opt_ao.0, opt_ao.1, opt_ao.2);
+    opts.optflag(opt_kc.0, opt_kc.1, opt_kc.2);
+    opts.optflag(opt_cb.0, opt_cb.1, opt_cb.2);
+    opts.optflag(opt_ds.0, opt_ds.1, opt_ds.2);
+    opts.optflag(opt_lc.0, opt_lc.1, opt_lc.2);
+    opts.optflag(opt_uc.0, opt_uc.1, opt_uc.2);
+    opts.optflag(opt_tic.0, opt_tic.1, opt_tic.2);
     opts.optflag(opt_h.0, opt_h.1, opt_h.2);
-    let matches = match opts.parse(&args[1..]) {
+    opts.optflag(opt_ver.0, opt_ver.1, opt_ver.2);
+    let mut args = vec![program.clone()];
+    match expand_response_files(&raw_args[1..], Path::new(""), &mut Vec::new()) {
+      Ok(expanded) => args.extend(expanded),
+      Err(err) => return Err(ParseOutcome::Error(err))
+    }
+    // a missing subcommand means "gen", so that "subquote FILE" keeps working
+    let (command, option_args) = match args.get(1).map(String::as_str) {
+      Some("gen") => (Command::Generate, &args[2..]),
+      Some("build") => (Command::Build, &args[2..]),
+      Some("stats") => (Command::Stats, &args[2..]),
+      Some("verify") => (Command::Verify, &args[2..]),
+      _ => (Command::Generate, &args[1..])
+    };
+    let matches = match opts.parse(option_args) {
         Ok(opt) => opt,
         Err(_) => {
           return Err(ParseOutcome::Error(
           String::from("found incomplete or unsupported arguments")))
         }
     };
-    if matches.opt_present(opt_h.0) {
-        print_usage(&program, opts);
-        return Err(ParseOutcome::Help);
+    if matches.opt_present(opt_h.1) || matches.opt_present(opt_hf.1) {
+        let usage = usage(program, &opts);
+        return match matches.opt_str(opt_hf.1).as_deref().map(str::trim) {
+          None | Some("brief") => Err(ParseOutcome::Help(usage)),
+          Some("examples") => Err(ParseOutcome::Help(format!(
+            "{}\n{}", usage, usage_examples(program, opt_u.1, opt_l.1, opt_rec.1, opt_glob.1)
+          ))),
+          Some(other) => Err(ParseOutcome::Error(
+            format!("couldn't parse specified {} (got \"{}\")", &opt_hf.1, other))
+          )
+        };
+    }
+    if matches.opt_present(opt_ver.1) {
+        return Err(ParseOutcome::Version);
+    }
+    // each subcommand has its own options, on top of the cache, verbose, help and version ones
+    let dictionary_opts = [
+      opt_u.1, opt_l.1, opt_x.1, opt_p.1, opt_enc.1, opt_fmt.1, opt_from.1, opt_to.1, opt_sb.1, opt_cb.1,
+      opt_spk.1, opt_tok.1, opt_skip.1, opt_prune.1, opt_kd.1, opt_kc.1, opt_ds.1, opt_rec.1, opt_glob.1
+    ];
+    let cache_opts = [opt_df.1, opt_cf.1, opt_json.1];
+    let build_opts = [opt_app.1];
+    let generation_opts = [
+      opt_s.1, opt_m.1, opt_div.1, opt_nrw.1, opt_r.1, opt_d.1, opt_min.1, opt_seed.1, opt_t.1, opt_cont.1,
+      opt_nsw.1, opt_inc.1, opt_end.1, opt_out.1, opt_ao.1, opt_pv.1, opt_pk.1, opt_succ.1, opt_stop.1,
+      opt_tc.1, opt_ss.1, opt_repl.1, opt_trace.1, opt_na.1, opt_nc.1, opt_long.1, opt_lc.1, opt_uc.1, opt_tic.1
+    ];
+    let stats_opts = [opt_var.1, opt_nsw.1];
+    let command_opts: Vec<&str> = match command {
+      Command::Generate => [&dictionary_opts[..], &cache_opts, &generation_opts].concat(),
+      Command::Build => [&dictionary_opts[..], &cache_opts, &build_opts].concat(),
+      Command::Stats => [&dictionary_opts[..], &stats_opts].concat(),
+      Command::Verify => Vec::new()
+    };
+    if let Some(opt) = dictionary_opts.iter().chain(cache_opts.iter()).chain(build_opts.iter())
+      .chain(generation_opts.iter()).chain(stats_opts.iter())
+      .find(|opt| !command_opts.contains(opt) && matches.opt_present(opt)) {
+      return Err(ParseOutcome::Error(format!("option \"{}\" can't be used with subcommand \"{}\"", opt, command)));
     }
 
-    let verbosity = match matches.opt_present(opt_v.0) {
+    let verbosity = match matches.opt_present(opt_v.1) {
       true => ArgProvided::Yes(!def_verbosity),
       false => ArgProvided::No(def_verbosity)
     };
@@ -283,24 +1618,57 @@ pub mod io {
       true => ArgProvided::Yes(!def_no_cache),
       false => ArgProvided::No(def_no_cache)
     };
-    let unit = match matches.opt_str(opt_u.0) {
-      Some(unit) => {
-        match unit.as_str() {
-          "word" => ArgProvided::Yes(Unit::Word),
-          "char" => ArgProvided::Yes(Unit::Grapheme),
-          _ => return Err(ParseOutcome::Error(
-            format!("couldn't parse specified {}", &opt_u.1))
+    let mut units: Vec<Unit> = Vec::new();
+    if let Some(unit_list) = matches.opt_str(opt_u.1) {
+      for unit in unit_list.split(',') {
+        let unit = match unit.trim() {
+          "word" => Unit::Word,
+          "char" => Unit::Grapheme,
+          other => return Err(ParseOutcome::Error(
+            format!("couldn't parse specified {} (got \"{}\")", &opt_u.1, other))
           )
+        };
+        if !units.contains(&unit) {
+          units.push(unit);
         }
-      },
+      }
+    }
+    let extra_units = units.split_off(units.len().min(1));
+    let unit = match units.pop() {
+      Some(unit) => ArgProvided::Yes(unit),
       None => ArgProvided::No(def_unit)
     };
-    let quote_length = match matches.opt_str(opt_l.0) {
-      Some(len) => match len.parse::<i32>() {
-        Ok(len) => ArgProvided::Yes(len),
-        Err(_) => return Err(ParseOutcome::Error(
-          format!("couldn't parse specified {}", &opt_l.1))
-        )
+    let ceiling = |len: i32| match len > MAX_QUOTE_LENGTH {
+      true => Err(ParseOutcome::Error(format!(
+        "quote length can't be greater than {} (got \"{}\", use 0 or \"max\" for as long as possible)",
+        MAX_QUOTE_LENGTH,
+        len
+      ))),
+      false => Ok(len)
+    };
+    let parse_length = |len: &str| match len {
+      "max" => Ok(0),
+      len => match len.parse::<i32>() {
+        Ok(len) => ceiling(len),
+        Err(err) => match err.kind() {
+          IntErrorKind::PosOverflow | IntErrorKind::NegOverflow => Err(ParseOutcome::Error(
+            format!("specified {} is out of range (use 0 or \"max\" for as long as possible)", &opt_l.1))
+          ),
+          _ => Err(ParseOutcome::Error(
+            format!("couldn't parse specified {} (expected a number, a range or \"max\")", &opt_l.1))
+          )
+        }
+      }
+    };
+    let mut max_quote_length = None;
+    let quote_length = match matches.opt_str(opt_l.1) {
+      // a leading dash is a negative length rather than a range
+      Some(len) => match len.char_indices().find(|(index, c)| *index > 0 && *c == '-') {
+        Some((index, _)) => {
+          max_quote_length = Some(parse_length(&len[index + 1..])?);
+          ArgProvided::Yes(parse_length(&len[..index])?)
+        },
+        None => ArgProvided::Yes(parse_length(&len)?)
       },
       None => {
         match unit.get_value() {
@@ -310,7 +1678,7 @@ pub mod io {
       }
     };
     let cache_directory = match matches.opt_str(opt_c.1) {
-      Some(dir) => ArgProvided::Yes(dir),
+      Some(dir) => ArgProvided::Yes(PathBuf::from(dir)),
       None => match def_cache_directory {
         Some(dir) => ArgProvided::No(dir),
         None => {
@@ -322,259 +1690,2657 @@ pub mod io {
               )
             ))
           }
-          ArgProvided::No(String::new())
+          ArgProvided::No(PathBuf::new())
         }
       }
     };
-    let subtitle = if !matches.free.is_empty() {
-      matches.free[0].clone()
+    // the caches are verified on their own
+    let subtitles = if !matches.free.is_empty() || command == Command::Verify {
+      matches.free.iter().map(PathBuf::from).collect()
+    } else if command != Command::Generate && Path::new(&args[1]).exists() {
+      return Err(ParseOutcome::Error(format!(
+        "subtitle file is requiered (\"{0}\" is a subcommand, use \"gen {0}\" or \"-- {0}\" for the file)", command
+      )))
     } else {
       return Err(ParseOutcome::Error(
         String::from("subtitle file is requiered"))
       )
     };
 
-    Ok(UnsafeArguments::new(subtitle, quote_length, verbosity, cache_directory, unit, no_cache))
-  }
-}
-
-pub mod builder {
-  use std::fs;
-  use std::path;
-  use std::collections::HashMap;
-  use std::io::BufReader;
-  use regex::Regex;
-  use serde::{Serialize, Deserialize};
-  use serde_json;
-  use rand::Rng;
-  use super::io::{SafeArguments, Unit};
-  use super::builder::Quote::{Node, Nil};
-
-  #[derive(Serialize, Deserialize)]
-  struct Entries {
-    entries: Vec<Entry>
-  }
-
-  impl Entries {
-    fn new() -> Self {
-      Self {
-        entries: Vec::new()
-      }
-    }
-    fn add_entry(&mut self, entry: Entry) {
-      self.entries.push(entry)
-    }
-  }
-
-  #[derive(Serialize, Deserialize)]
-  struct Entry {
-    key: String,
-    pairs: Vec<String>
-  }
-
-  impl Entry {
-    fn new(key: String, pairs: Vec<String>) -> Self {
-      Self { key, pairs }
-    }
-  }
-
-  enum Quote {
-    Node(String, Box<Quote>),
-    Nil
-  }
-
-  impl Quote {
-    fn to_vec(&self) -> Option<Vec<String>> {
-      match self {
-        Node(unit, next) => {
-          match next.to_vec() {
-            Some(mut remaining) => {
-              let mut current = vec![unit.clone()];
-              current.append(remaining.as_mut());
-              Some(current)
-            },
-            None => {
-              Some(vec![unit.clone()])
-            }
-          }
-        },
-        Nil => None
-      }
-    }
-  }
+    let separator = match matches.opt_str(opt_s.1) {
+      Some(sep) => ArgProvided::Yes(sep),
+      None => ArgProvided::No(UnsafeArguments::get_default_separator(Some(unit.get_value().clone())))
+    };
 
-  pub fn get_quote(args: SafeArguments) -> Result<String, String> {
-    let mut cached_dict = path::PathBuf::from(&args.cache_directory);
-    let mut split_subtitle_path: Vec<&str> = args.subtitle.split("/").collect();
-    let subtitle = match split_subtitle_path.pop() {
-      Some(filename) => filename,
-      None => return Err(String::from("couldn't determine subtitle filename"))
+    let no_immediate_repeat = match matches.opt_present(opt_r.1) {
+      true => ArgProvided::Yes(!def_no_immediate_repeat),
+      false => ArgProvided::No(def_no_immediate_repeat)
     };
-    cached_dict.push(subtitle);
-    let _ = match args.unit {
-      Unit::Word => cached_dict.set_extension("word"),
-      Unit::Grapheme => cached_dict.set_extension("char"),
+    let dump_dialogue = match matches.opt_present(opt_d.1) {
+      true => ArgProvided::Yes(!def_dump_dialogue),
+      false => ArgProvided::No(def_dump_dialogue)
     };
-    if !args.no_cache && cached_dict.is_file() {
-      let dict = match load_dict(cached_dict) {
-        Ok(de_dict) => de_dict,
-        Err(err) => return Err(err)
-      };
-      match generate_quote(&dict, args.quote_length) {
-        Ok(quote) => Ok(quote),
-        Err(err) => Err(err)
-      }
-    } else {
-      let mut dict: HashMap<String, Vec<String>> = HashMap::new();
-      match fs::read_to_string(&args.subtitle) {
-        Err(err) => {
-          return Err(format!("couldn't open subtitle file: {}", err))
-        },
-        Ok(subtitle) => {
-          let subrip_reg = Regex::new(r"(^\d{2}:\d{2}:\d{2},\d{3}\s-->\s\d{2}:\d{2}:\d{2},\d{3}$)|(^\d+$)|(^()$)")
-            .unwrap();
-          let noise_reg = Regex::new(r#""\s?|<.*>\s?|\[.*\]|\(.*\)|^-?\s?[a-zA-Z]+:\s?"#)
-            .unwrap();
-          let punct_reg = Regex::new(r",|-|\.|;|\?|!")
-            .unwrap();
-          for line in subtitle.lines() {
-            if !subrip_reg.is_match(line) {
-              let replaced_noise = noise_reg.replace_all(line, "");
-              let replaced = punct_reg.replace_all(replaced_noise.as_ref(), "");
-              let units = match args.unit {
-                Unit::Word => replaced.split_whitespace(),
-                Unit::Grapheme => replaced.split_whitespace()
-              };
-              let mut iter = units.peekable();
-              loop {
-                let next = match iter.next() {
-                  Some(next) => String::from(next),
-                  None => break
-                };
-                let peeked = match iter.peek() {
-                  Some(next) => String::from(*next),
-                  None => break
-                };
-                match dict.get_mut(&next) {
-                  Some(entry) => {
-                    entry.push(peeked)
-                  },
-                  None => {
-                    match dict.insert(next, vec![peeked]) {
-                      _ => ()
-                    }
-                  }
-                }
-              }
-            }
-          }
+    let keep_punctuation = match matches.opt_present(opt_p.1) {
+      true => ArgProvided::Yes(!def_keep_punctuation),
+      false => ArgProvided::No(def_keep_punctuation)
+    };
+    let with_timecode = match matches.opt_present(opt_tc.1) {
+      true => ArgProvided::Yes(!def_with_timecode),
+      false => ArgProvided::No(def_with_timecode)
+    };
+    let sentence_boundaries = match matches.opt_present(opt_sb.1) {
+      true => ArgProvided::Yes(!def_sentence_boundaries),
+      false => ArgProvided::No(def_sentence_boundaries)
+    };
+    let smart_start = match matches.opt_present(opt_ss.1) {
+      true => ArgProvided::Yes(!def_smart_start),
+      false => ArgProvided::No(def_smart_start)
+    };
+    let repl = match matches.opt_present(opt_repl.1) {
+      true => ArgProvided::Yes(!def_repl),
+      false => ArgProvided::No(def_repl)
+    };
+    let trace = match matches.opt_present(opt_trace.1) {
+      true => ArgProvided::Yes(!def_trace),
+      false => ArgProvided::No(def_trace)
+    };
+    let estimate_variety = match matches.opt_present(opt_var.1) {
+      true => ArgProvided::Yes(!def_estimate_variety),
+      false => ArgProvided::No(def_estimate_variety)
+    };
+    let prune = match matches.opt_present(opt_prune.1) {
+      true => ArgProvided::Yes(!def_prune),
+      false => ArgProvided::No(def_prune)
+    };
+    let no_append = match matches.opt_present(opt_na.1) {
+      true => ArgProvided::Yes(!def_no_append),
+      false => ArgProvided::No(def_no_append)
+    };
+    let no_capitalize = match matches.opt_present(opt_nc.1) {
+      true => ArgProvided::Yes(!def_no_capitalize),
+      false => ArgProvided::No(def_no_capitalize)
+    };
+    let keep_duplicates = match matches.opt_present(opt_kd.1) {
+      true => ArgProvided::Yes(!def_keep_duplicates),
+      false => ArgProvided::No(def_keep_duplicates)
+    };
+    let recursive = match matches.opt_present(opt_rec.1) {
+      true => ArgProvided::Yes(!def_recursive),
+      false => ArgProvided::No(def_recursive)
+    };
+    let longest = match matches.opt_present(opt_long.1) {
+      true => ArgProvided::Yes(!def_longest),
+      false => ArgProvided::No(def_longest)
+    };
+    let append_output = match matches.opt_present(opt_ao.1) {
+      true => ArgProvided::Yes(!def_append_output),
+      false => ArgProvided::No(def_append_output)
+    };
+    let keep_captions = match matches.opt_present(opt_kc.1) {
+      true => ArgProvided::Yes(!def_keep_captions),
+      false => ArgProvided::No(def_keep_captions)
+    };
+    let cue_boundaries = match matches.opt_present(opt_cb.1) {
+      true => ArgProvided::Yes(!def_cue_boundaries),
+      false => ArgProvided::No(def_cue_boundaries)
+    };
+    let drop_spaces = match matches.opt_present(opt_ds.1) {
+      true => ArgProvided::Yes(!def_drop_spaces),
+      false => ArgProvided::No(def_drop_spaces)
+    };
+    let case: Vec<Case> = [(opt_lc, Case::Lower), (opt_uc, Case::Upper), (opt_tic, Case::Title)].iter()
+      .filter(|(opt, _)| matches.opt_present(opt.1))
+      .map(|(_, case)| *case)
+      .collect();
+    let min_length = match matches.opt_str(opt_min.1) {
+      Some(len) => match len.parse::<i32>() {
+        Ok(len) => ArgProvided::Yes(ceiling(len)?),
+        Err(_) => return Err(ParseOutcome::Error(
+          format!("couldn't parse specified {}", &opt_min.1))
+        )
+      },
+      None => ArgProvided::No(def_min_length)
+    };
+    let seed = match matches.opt_str(opt_seed.1) {
+      Some(seed) => match seed.parse::<u64>() {
+        Ok(seed) => Some(seed),
+        Err(_) => return Err(ParseOutcome::Error(
+          format!("couldn't parse specified {}", &opt_seed.1))
+        )
+      },
+      None => None
+    };
+    let extract_cmd = matches.opt_str(opt_x.1);
+    let template = matches.opt_str(opt_t.1);
+    let continue_phrase = matches.opt_str(opt_cont.1);
+    let successors = matches.opt_str(opt_succ.1);
+    let no_start_words = matches.opt_str(opt_nsw.1).map(PathBuf::from);
+    let append_cache = matches.opt_str(opt_app.1).map(PathBuf::from);
+    let cache_file = matches.opt_str(opt_cf.1).map(PathBuf::from);
+    let output_file = matches.opt_str(opt_out.1).map(PathBuf::from);
+    let json_dict_out = matches.opt_str(opt_json.1).map(PathBuf::from);
+    let glob = matches.opt_str(opt_glob.1);
+    let stop_at: Vec<String> = matches.opt_strs(opt_stop.1).iter().map(|word| String::from(word.trim())).collect();
+    let speaker = matches.opt_str(opt_spk.1).map(|speaker| String::from(speaker.trim()));
+    let must_include = matches.opt_str(opt_inc.1).map(|word| String::from(word.trim()));
+    let end_word = matches.opt_str(opt_end.1).map(|word| String::from(word.trim()));
+    let mut times: Vec<Option<Duration>> = Vec::new();
+    for opt_time in [opt_from, opt_to].iter() {
+      times.push(match matches.opt_str(opt_time.1) {
+        Some(time) => match parse_time(&time) {
+          Some(time) => Some(time),
+          None => return Err(ParseOutcome::Error(
+            format!("couldn't parse specified {} (expected {})", &opt_time.1, &opt_time.3))
+          )
+        },
+        None => None
+      });
+    }
+    let (from_time, to_time) = (times[0], times[1]);
+    let skip_cues = match matches.opt_str(opt_skip.1) {
+      Some(ranges) => match parse_cue_ranges(&ranges) {
+        Some(ranges) => ranges,
+        None => return Err(ParseOutcome::Error(
+          format!("couldn't parse specified {} (expected numbers or ranges like \"1,5,10-12\")", &opt_skip.1))
+        )
+      },
+      None => Vec::new()
+    };
+    let encoding = match matches.opt_str(opt_enc.1) {
+      Some(label) => match Encoding::for_label(label.trim().as_bytes()) {
+        Some(encoding) => Some(encoding),
+        None => return Err(ParseOutcome::Error(
+          format!("couldn't parse specified {} (got \"{}\")", &opt_enc.1, label))
+        )
+      },
+      None => None
+    };
+    let format = match matches.opt_str(opt_fmt.1).as_deref().map(str::trim) {
+      None | Some("auto") => None,
+      Some("srt") => Some(Format::Srt),
+      Some("vtt") => Some(Format::Vtt),
+      Some("ass") | Some("ssa") => Some(Format::Ass),
+      Some("text") | Some("txt") => Some(Format::Text),
+      Some(other) => return Err(ParseOutcome::Error(
+        format!("couldn't parse specified {} (got \"{}\")", &opt_fmt.1, other))
+      )
+    };
+    let dict_format = match matches.opt_str(opt_df.1).as_deref().map(str::trim) {
+      None => UnsafeArguments::get_default_dict_format(),
+      Some("json") => DictFormat::Json,
+      Some("binary") => DictFormat::Binary,
+      Some(other) => return Err(ParseOutcome::Error(
+        format!("couldn't parse specified {} (got \"{}\")", &opt_df.1, other))
+      )
+    };
+    let tokenizer = match matches.opt_str(opt_tok.1) {
+      None => None,
+      Some(tokenizer) => match tokenizer.trim() {
+        "whitespace" => Some(Tokenizer::Whitespace),
+        "unicode-words" => Some(Tokenizer::UnicodeWords),
+        other => match other.strip_prefix("regex:").map(Regex::new) {
+          Some(Ok(reg)) => Some(Tokenizer::Pattern(reg)),
+          Some(Err(err)) => return Err(ParseOutcome::Error(
+            format!("couldn't parse specified {} pattern: {}", &opt_tok.1, err))
+          ),
+          None => return Err(ParseOutcome::Error(
+            format!("couldn't parse specified {} (got \"{}\")", &opt_tok.1, other))
+          )
+        }
+      }
+    };
+    let max_chars = match matches.opt_str(opt_m.1) {
+      Some(max) => match max.parse::<usize>() {
+        Ok(max) => Some(max),
+        Err(_) => return Err(ParseOutcome::Error(
+          format!("couldn't parse specified {}", &opt_m.1))
+        )
+      },
+      None => None
+    };
+    let preview = match matches.opt_str(opt_pv.1) {
+      Some(count) => match count.trim().parse::<usize>() {
+        Ok(count) => Some(count),
+        Err(_) => return Err(ParseOutcome::Error(
+          format!("couldn't parse specified {} (got \"{}\")", &opt_pv.1, count))
+        )
+      },
+      None => None
+    };
+    let pick = match matches.opt_str(opt_pk.1) {
+      Some(pick) => match pick.trim().parse::<usize>() {
+        Ok(pick) => Some(pick),
+        Err(_) => return Err(ParseOutcome::Error(
+          format!("couldn't parse specified {} (got \"{}\")", &opt_pk.1, pick))
+        )
+      },
+      None => None
+    };
+    let no_repeat_window = match matches.opt_str(opt_nrw.1) {
+      Some(window) => match window.trim().parse::<usize>() {
+        Ok(window) => Some(window),
+        Err(_) => return Err(ParseOutcome::Error(
+          format!("couldn't parse specified {} (got \"{}\")", &opt_nrw.1, window))
+        )
+      },
+      None => None
+    };
+    let min_diversity = match matches.opt_str(opt_div.1) {
+      Some(ratio) => match ratio.trim().parse::<f64>() {
+        Ok(ratio) if ratio.is_finite() => Some(ratio),
+        _ => return Err(ParseOutcome::Error(
+          format!("couldn't parse specified {} (got \"{}\")", &opt_div.1, ratio))
+        )
+      },
+      None => None
+    };
+
+    Ok(UnsafeArguments::new(
+      subtitles,
+      quote_length,
+      verbosity,
+      cache_directory,
+      unit,
+      extra_units,
+      no_cache,
+      separator,
+      extract_cmd,
+      max_chars,
+      no_immediate_repeat,
+      dump_dialogue,
+      keep_punctuation,
+      min_length,
+      seed,
+      template,
+      continue_phrase,
+      max_quote_length,
+      no_start_words,
+      encoding,
+      must_include,
+      from_time,
+      to_time,
+      command,
+      with_timecode,
+      case,
+      append_cache,
+      sentence_boundaries,
+      format,
+      smart_start,
+      repl,
+      trace,
+      speaker,
+      tokenizer,
+      estimate_variety,
+      skip_cues,
+      prune,
+      json_dict_out,
+      no_append,
+      no_capitalize,
+      keep_duplicates,
+      recursive,
+      glob,
+      stop_at,
+      longest,
+      min_diversity,
+      no_repeat_window,
+      cache_file,
+      output_file,
+      append_output,
+      keep_captions,
+      cue_boundaries,
+      preview,
+      pick,
+      drop_spaces,
+      successors,
+      end_word,
+      dict_format
+    ))
+  }
+}
+
+pub mod builder {
+  use std::fmt;
+  use std::fs;
+  use std::borrow::Cow;
+  use std::sync::Arc;
+  use std::time::{Duration, Instant, SystemTime};
+  use std::path::{self, PathBuf};
+  use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+  use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+  use std::process::Command;
+  use std::ffi::OsStr;
+  use regex::Regex;
+  use flate2::read::GzDecoder;
+  use serde::{Serialize, Deserialize};
+  use serde_json;
+  use rand::{Rng, SeedableRng};
+  use rand_chacha::ChaCha20Rng;
+  use unicode_segmentation::UnicodeSegmentation;
+  use encoding_rs::{Encoding, UTF_8};
+  #[cfg(feature = "parallel")]
+  use rayon::prelude::*;
+  use super::io::{titlecase, DictFormat, Format, SafeArguments, Tokenizer, UnsafeArguments, Unit, MAX_QUOTE_LENGTH};
+
+  const UNBOUNDED_LENGTH_CAP: usize = MAX_QUOTE_LENGTH as usize;
+  const BRANCH_ATTEMPTS: usize = 10;
+  const LONGEST_SEARCH_STEPS: usize = 100_000;
+  // starts the error of a subtitle without dialogue, an empty corpus rather than a failed read
+  pub const NO_DIALOGUE: &str = "subtitle file contains no dialogue";
+
+  // the binary cache layout, each unit being stored once and referred to by its id
+  #[derive(Serialize, Deserialize)]
+  struct Interned {
+    units: Vec<String>,
+    successors: Vec<Vec<u32>>,
+    timecodes: Vec<(u32, u64)>,
+    starters: Vec<u32>
+  }
+
+  #[derive(Serialize, Deserialize)]
+  struct Entries {
+    entries: Vec<Entry>
+  }
+
+  impl Entries {
+    fn new() -> Self {
+      Self {
+        entries: Vec::new()
+      }
+    }
+    fn add_entry(&mut self, entry: Entry) {
+      self.entries.push(entry)
+    }
+  }
+
+  #[derive(Serialize, Deserialize)]
+  struct Entry {
+    key: String,
+    pairs: Vec<String>,
+    // milliseconds, missing from caches written before timecodes were recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timecode: Option<u64>,
+    // only written for dictionaries built with sentence boundaries
+    #[serde(default, skip_serializing_if = "is_false")]
+    start: bool
+  }
+
+  impl Entry {
+    fn new(key: String, pairs: Vec<String>, timecode: Option<u64>, start: bool) -> Self {
+      Self { key, pairs, timecode, start }
+    }
+  }
+
+  fn is_false(value: &bool) -> bool {
+    !*value
+  }
+
+  // units are interned once and transitions refer to them by index, so that a unit following
+  // many others isn't stored again in every successor list
+  #[derive(Clone, Debug, Default)]
+  pub struct MarkovDict {
+    units: Vec<String>,
+    ids: HashMap<String, u32>,
+    successors: Vec<Vec<u32>>,
+    // start of the first cue each key was found in
+    timecodes: HashMap<u32, Duration>,
+    // units starting a sentence, when the dictionary was built with sentence boundaries
+    starters: HashSet<u32>,
+    // units followed by at least one other, counted as their first successor is added
+    keys: usize
+  }
+
+  impl MarkovDict {
+    pub fn new() -> Self {
+      Self::default()
+    }
+    pub fn add_transition(&mut self, unit: &str, next: &str) {
+      let (id, next) = (self.intern(unit), self.intern(next));
+      if self.successors[id as usize].is_empty() {
+        self.keys += 1;
+      }
+      self.successors[id as usize].push(next);
+    }
+    // only the first occurrence of a unit is kept
+    pub fn add_timecode(&mut self, unit: &str, timecode: Duration) {
+      let id = self.intern(unit);
+      self.timecodes.entry(id).or_insert(timecode);
+    }
+    pub fn add_starter(&mut self, unit: &str) {
+      let id = self.intern(unit);
+      self.starters.insert(id);
+    }
+    pub fn is_starter(&self, unit: &str) -> bool {
+      self.id(unit).is_some_and(|id| self.starters.contains(&id))
+    }
+    pub fn timecode(&self, unit: &str) -> Option<Duration> {
+      self.id(unit).and_then(|id| self.timecodes.get(&id).cloned())
+    }
+    // number of units followed by at least one other
+    pub fn len(&self) -> usize {
+      self.keys
+    }
+    pub fn is_empty(&self) -> bool {
+      self.keys == 0
+    }
+    pub fn transitions(&self) -> usize {
+      self.successors.iter().map(Vec::len).sum()
+    }
+    pub fn contains(&self, unit: &str) -> bool {
+      self.next_ids(unit).is_some()
+    }
+    pub fn keys(&self) -> Vec<&str> {
+      self.key_ids().map(|id| self.unit(id)).collect()
+    }
+    pub fn successors(&self, unit: &str) -> Option<Vec<&str>> {
+      self.next_ids(unit).map(|next| next.iter().map(|id| self.unit(*id)).collect())
+    }
+    // each distinct successor once, the most frequent first
+    pub fn successor_counts(&self, unit: &str) -> Option<Vec<(&str, usize)>> {
+      let next = self.next_ids(unit)?;
+      let mut counts: Vec<(&str, usize)> = Vec::new();
+      for id in next.iter() {
+        match counts.iter_mut().find(|(next, _)| *next == self.unit(*id)) {
+          Some((_, count)) => *count += 1,
+          None => counts.push((self.unit(*id), 1))
+        }
+      }
+      counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+      Some(counts)
+    }
+    pub fn merge(&mut self, other: &MarkovDict) {
+      for id in other.key_ids() {
+        for next in other.successors[id as usize].iter() {
+          self.add_transition(other.unit(id), other.unit(*next));
+        }
+        if let Some(timecode) = other.timecodes.get(&id) {
+          self.add_timecode(other.unit(id), *timecode);
+        }
+        if other.starters.contains(&id) {
+          self.add_starter(other.unit(id));
         }
       }
-      if args.no_cache {
-        match generate_quote(&dict, args.quote_length) {
-          Ok(quote) => Ok(quote),
-          Err(err) => Err(err)
-        }
-      } else {
-        match save_dict(dict.clone(), &cached_dict) {
-          Ok(_) => {
-            match generate_quote(&dict, args.quote_length) {
-              Ok(quote) => Ok(quote),
-              Err(err) => Err(err)
+      self.sort();
+    }
+    // the cache file format, readable back with `read_from`
+    pub fn write_to<W: Write>(&self, writer: W) -> Result<(), String> {
+      match serde_json::to_writer(writer, &self.entries()) {
+        Ok(_) => Ok(()),
+        Err(_) => Err(String::from("couldn't write dictionary"))
+      }
+    }
+    // the binary cache file format, readable back with `read_binary`
+    pub fn write_binary<W: Write>(&self, writer: W) -> Result<(), String> {
+      let mut timecodes: Vec<(u32, u64)> = self.timecodes.iter()
+        .map(|(id, timecode)| (*id, timecode.as_millis() as u64))
+        .collect();
+      timecodes.sort_unstable();
+      let mut starters: Vec<u32> = self.starters.iter().cloned().collect();
+      starters.sort_unstable();
+      let interned = Interned {
+        units: self.units.clone(),
+        successors: self.successors.clone(),
+        timecodes,
+        starters
+      };
+      match serde_cbor::to_writer(writer, &interned) {
+        Ok(_) => Ok(()),
+        Err(_) => Err(String::from("couldn't write dictionary"))
+      }
+    }
+    fn entries(&self) -> Entries {
+      let mut entries = Entries::new();
+      // sorted so that identical dictionaries always serialize to identical bytes
+      let mut keys: Vec<&str> = self.keys();
+      keys.sort();
+      for key in keys {
+        if let Some(pairs) = self.successors(key) {
+          let timecode = self.timecode(key).map(|timecode| timecode.as_millis() as u64);
+          let pairs = pairs.into_iter().map(String::from).collect();
+          entries.add_entry(Entry::new(String::from(key), pairs, timecode, self.is_starter(key)));
+        }
+      }
+      entries
+    }
+    // the export format, meant for other tools: {"key": {"successor": count, ...}, ...}
+    pub fn write_json<W: Write>(&self, writer: W) -> Result<(), String> {
+      let mut transitions: BTreeMap<&str, BTreeMap<&str, usize>> = BTreeMap::new();
+      for id in self.key_ids() {
+        let counts = transitions.entry(self.unit(id)).or_default();
+        for next in self.successors[id as usize].iter() {
+          *counts.entry(self.unit(*next)).or_default() += 1;
+        }
+      }
+      match serde_json::to_writer_pretty(writer, &transitions) {
+        Ok(_) => Ok(()),
+        Err(_) => Err(String::from("couldn't write dictionary"))
+      }
+    }
+    pub fn read_from<R: Read>(reader: R) -> Result<MarkovDict, String> {
+      match serde_json::from_reader(reader) {
+        Ok(de_dict) => Ok(MarkovDict::from_entries(de_dict)),
+        Err(_) => Err(String::from("couldn't deserialize dictionary"))
+      }
+    }
+    // the units are taken as they were interned, rather than interned again one transition at a time
+    pub fn read_binary<R: Read>(reader: R) -> Result<MarkovDict, String> {
+      let interned: Interned = match serde_cbor::from_reader(reader) {
+        Ok(interned) => interned,
+        Err(_) => return Err(String::from("couldn't deserialize dictionary"))
+      };
+      let count = interned.units.len();
+      let ids: HashMap<String, u32> = interned.units.iter().enumerate().map(|(id, unit)| (unit.clone(), id as u32)).collect();
+      let valid = |id: &u32| (*id as usize) < count;
+      if ids.len() != count
+        || interned.successors.len() != count
+        || !interned.successors.iter().flatten().all(valid)
+        || !interned.timecodes.iter().map(|(id, _)| id).all(valid)
+        || !interned.starters.iter().all(valid) {
+        return Err(String::from("couldn't deserialize dictionary"));
+      }
+      let keys = interned.successors.iter().filter(|successors| !successors.is_empty()).count();
+      let mut dict = MarkovDict {
+        units: interned.units,
+        ids,
+        successors: interned.successors,
+        timecodes: interned.timecodes.into_iter().map(|(id, timecode)| (id, Duration::from_millis(timecode))).collect(),
+        starters: interned.starters.into_iter().collect(),
+        keys
+      };
+      dict.sort();
+      Ok(dict)
+    }
+    fn from_entries(de_dict: Entries) -> MarkovDict {
+      let mut dict = MarkovDict::new();
+      for entry in de_dict.entries.iter() {
+        for pair in entry.pairs.iter() {
+          dict.add_transition(&entry.key, pair);
+        }
+        if let Some(timecode) = entry.timecode {
+          dict.add_timecode(&entry.key, Duration::from_millis(timecode));
+        }
+        if entry.start {
+          dict.add_starter(&entry.key);
+        }
+      }
+      dict.sort();
+      dict
+    }
+    // keeps the keys reachable from `starts` in at most `steps` transitions, returns the number of
+    // keys dropped
+    pub fn prune(&mut self, starts: &[&str], steps: usize) -> usize {
+      let mut reached: HashSet<u32> = starts.iter().filter_map(|start| self.id(start)).collect();
+      let mut frontier: Vec<u32> = reached.iter().cloned().collect();
+      for _ in 0..steps {
+        if frontier.is_empty() {
+          break;
+        }
+        let mut next_frontier: Vec<u32> = Vec::new();
+        for id in frontier.iter() {
+          for next in self.successors[*id as usize].iter() {
+            if reached.insert(*next) {
+              next_frontier.push(*next);
+            }
+          }
+        }
+        frontier = next_frontier;
+      }
+      let unreachable: Vec<u32> = self.key_ids().filter(|id| !reached.contains(id)).collect();
+      for id in unreachable.iter() {
+        self.successors[*id as usize].clear();
+        self.timecodes.remove(id);
+        self.starters.remove(id);
+      }
+      self.keys -= unreachable.len();
+      unreachable.len()
+    }
+    pub fn reverse(&self) -> MarkovDict {
+      let mut reverse = MarkovDict::new();
+      for id in self.key_ids() {
+        for next in self.successors[id as usize].iter() {
+          reverse.add_transition(self.unit(*next), self.unit(id));
+        }
+      }
+      reverse.sort();
+      reverse
+    }
+    fn intern(&mut self, unit: &str) -> u32 {
+      if let Some(id) = self.ids.get(unit) {
+        return *id;
+      }
+      let id = self.units.len() as u32;
+      self.units.push(String::from(unit));
+      self.ids.insert(String::from(unit), id);
+      self.successors.push(Vec::new());
+      id
+    }
+    fn unit(&self, id: u32) -> &str {
+      &self.units[id as usize]
+    }
+    fn units_of(&self, ids: &[u32]) -> Vec<String> {
+      ids.iter().map(|id| String::from(self.unit(*id))).collect()
+    }
+    fn id(&self, unit: &str) -> Option<u32> {
+      self.ids.get(unit).cloned()
+    }
+    fn key_ids(&self) -> impl Iterator<Item = u32> + '_ {
+      (0..self.units.len() as u32).filter(move |id| !self.successors[*id as usize].is_empty())
+    }
+    fn next_ids(&self, unit: &str) -> Option<&[u32]> {
+      match self.id(unit) {
+        Some(id) if !self.successors[id as usize].is_empty() => Some(&self.successors[id as usize]),
+        _ => None
+      }
+    }
+    // successors are kept sorted so that built and cached dictionaries are interchangeable
+    fn sort(&mut self) {
+      let units = &self.units;
+      for successors in self.successors.iter_mut() {
+        successors.sort_by(|a, b| units[*a as usize].cmp(&units[*b as usize]));
+      }
+    }
+  }
+
+  // dictionaries are equal when they hold the same transitions, whatever the interning order
+  impl PartialEq for MarkovDict {
+    fn eq(&self, other: &Self) -> bool {
+      self.len() == other.len() && self.key_ids().all(|id| {
+        other.successors(self.unit(id)) == self.successors(self.unit(id))
+      })
+    }
+  }
+
+  // dictionaries loaded from cache files, kept in memory for long running processes: an entry is
+  // reloaded when its file's modification time changes, and the least recently used one is evicted
+  // beyond `capacity` (at least 1)
+  pub struct DictCache {
+    capacity: usize,
+    entries: VecDeque<(PathBuf, SystemTime, Arc<MarkovDict>)>
+  }
+
+  impl DictCache {
+    pub fn new(capacity: usize) -> Self {
+      Self {
+        capacity: capacity.max(1),
+        entries: VecDeque::new()
+      }
+    }
+    pub fn len(&self) -> usize {
+      self.entries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+      self.entries.is_empty()
+    }
+    // the dictionary is shared with the cache, so that a hit never copies it
+    pub fn load(&mut self, cached_dict: &path::Path) -> Result<Arc<MarkovDict>, String> {
+      let modified = match fs::metadata(cached_dict).and_then(|metadata| metadata.modified()) {
+        Ok(modified) => modified,
+        Err(_) => return Err(String::from("couldn't open cached file"))
+      };
+      let index = self.entries.iter().position(|(path, _, _)| path == cached_dict);
+      let entry = match index.and_then(|index| self.entries.remove(index)) {
+        Some(entry) if entry.1 == modified => entry,
+        _ => (cached_dict.to_path_buf(), modified, Arc::new(load_dict(cached_dict.to_path_buf())?))
+      };
+      self.entries.push_front(entry);
+      self.entries.truncate(self.capacity);
+      Ok(Arc::clone(&self.entries[0].2))
+    }
+  }
+
+  // the last `window` quotes of a batch, so that a quote equal to one of them can be generated again
+  pub struct RecentQuotes {
+    window: usize,
+    quotes: VecDeque<String>
+  }
+
+  impl RecentQuotes {
+    pub fn new(window: usize) -> Self {
+      Self {
+        window,
+        quotes: VecDeque::new()
+      }
+    }
+    pub fn contains(&self, quote: &str) -> bool {
+      self.quotes.iter().any(|recent| recent == quote)
+    }
+    pub fn push(&mut self, quote: &str) {
+      self.quotes.push_back(String::from(quote));
+      while self.quotes.len() > self.window {
+        self.quotes.pop_front();
+      }
+    }
+  }
+
+  // a SubRip cue is an index line, a timecode line, then text lines up to a blank line
+  enum SubripState {
+    Index,
+    Timecode,
+    Text
+  }
+
+  struct Cue<'a> {
+    index: u64,
+    start: Duration,
+    lines: Vec<&'a str>
+  }
+
+  impl<'a> Cue<'a> {
+    fn new(index: u64, start: Duration) -> Self {
+      Self {
+        index,
+        start,
+        lines: Vec::new()
+      }
+    }
+    fn within(&self, from: Option<Duration>, to: Option<Duration>) -> bool {
+      from.is_none_or(|from| self.start >= from) && to.is_none_or(|to| self.start <= to)
+    }
+    fn skipped(&self, skip: &[(u64, u64)]) -> bool {
+      skip.iter().any(|(first, last)| (*first..=*last).contains(&self.index))
+    }
+  }
+
+  // there's no order() setter: dictionaries only map a unit to its successors, so chains are of order 1
+  pub struct QuoteConfig {
+    unit: Unit,
+    length: Option<i32>,
+    max_length: Option<i32>,
+    min_length: i32,
+    seed: Option<u64>,
+    separator: Option<String>,
+    max_chars: Option<usize>,
+    min_diversity: Option<f64>,
+    no_immediate_repeat: bool,
+    no_start_words: Vec<String>,
+    smart_start: bool,
+    trace: bool,
+    tokenizer: Tokenizer,
+    no_append: bool,
+    no_capitalize: bool,
+    stop_at: Vec<String>,
+    longest: bool
+  }
+
+  impl QuoteConfig {
+    pub fn new() -> Self {
+      Self {
+        unit: UnsafeArguments::get_default_unit(),
+        length: None,
+        max_length: None,
+        min_length: UnsafeArguments::get_default_min_length(),
+        seed: None,
+        separator: None,
+        max_chars: None,
+        min_diversity: None,
+        no_immediate_repeat: UnsafeArguments::get_default_no_immediate_repeat(),
+        no_start_words: Vec::new(),
+        smart_start: UnsafeArguments::get_default_smart_start(),
+        trace: UnsafeArguments::get_default_trace(),
+        tokenizer: Tokenizer::default(),
+        no_append: UnsafeArguments::get_default_no_append(),
+        no_capitalize: UnsafeArguments::get_default_no_capitalize(),
+        stop_at: Vec::new(),
+        longest: UnsafeArguments::get_default_longest()
+      }
+    }
+    pub fn unit(mut self, unit: Unit) -> Self {
+      self.unit = unit;
+      self
+    }
+    pub fn length(mut self, length: i32) -> Self {
+      self.length = Some(length);
+      self
+    }
+    pub fn length_range(mut self, min: i32, max: i32) -> Self {
+      self.length = Some(min);
+      self.max_length = Some(max);
+      self
+    }
+    pub fn min_length(mut self, min_length: i32) -> Self {
+      self.min_length = min_length;
+      self
+    }
+    pub fn seed(mut self, seed: u64) -> Self {
+      self.seed = Some(seed);
+      self
+    }
+    pub fn separator(mut self, separator: &str) -> Self {
+      self.separator = Some(String::from(separator));
+      self
+    }
+    pub fn max_chars(mut self, max_chars: usize) -> Self {
+      self.max_chars = Some(max_chars);
+      self
+    }
+    pub fn min_diversity(mut self, ratio: f64) -> Self {
+      self.min_diversity = Some(ratio);
+      self
+    }
+    pub fn no_immediate_repeat(mut self, no_immediate_repeat: bool) -> Self {
+      self.no_immediate_repeat = no_immediate_repeat;
+      self
+    }
+    pub fn no_start_words(mut self, words: &[&str]) -> Self {
+      self.no_start_words = words.iter().map(|word| word.to_lowercase()).collect();
+      self
+    }
+    pub fn smart_start(mut self, smart_start: bool) -> Self {
+      self.smart_start = smart_start;
+      self
+    }
+    pub fn trace(mut self, trace: bool) -> Self {
+      self.trace = trace;
+      self
+    }
+    pub fn tokenizer(mut self, tokenizer: Tokenizer) -> Self {
+      self.tokenizer = tokenizer;
+      self
+    }
+    pub fn no_append(mut self, no_append: bool) -> Self {
+      self.no_append = no_append;
+      self
+    }
+    pub fn no_capitalize(mut self, no_capitalize: bool) -> Self {
+      self.no_capitalize = no_capitalize;
+      self
+    }
+    pub fn stop_at(mut self, words: &[&str]) -> Self {
+      self.stop_at = words.iter().map(|word| word.to_lowercase()).collect();
+      self
+    }
+    pub fn longest(mut self, longest: bool) -> Self {
+      self.longest = longest;
+      self
+    }
+    // length and separator default to the unit's ones
+    fn quote_length(&self) -> i32 {
+      match self.length {
+        Some(length) => length,
+        None => UnsafeArguments::get_default_quote_length(Some(self.unit.clone()))
+      }
+    }
+    // lengths of a range are picked at or above the minimum length
+    fn pick_length(&self, rng: &mut ChaCha20Rng) -> i32 {
+      let length = self.quote_length();
+      match self.max_length {
+        Some(max_length) if max_length > length => {
+          rng.gen_range(length.max(self.min_length).min(max_length), max_length + 1)
+        },
+        _ => length
+      }
+    }
+    fn joiner(&self) -> String {
+      match &self.separator {
+        Some(separator) => separator.clone(),
+        None => UnsafeArguments::get_default_separator(Some(self.unit.clone()))
+      }
+    }
+    // pinned to chacha20 so that a seed gives the same quotes whatever the rand version
+    fn rng(&self) -> ChaCha20Rng {
+      match self.seed {
+        Some(seed) => ChaCha20Rng::seed_from_u64(seed),
+        None => ChaCha20Rng::from_entropy()
+      }
+    }
+  }
+
+  impl Default for QuoteConfig {
+    fn default() -> Self {
+      Self::new()
+    }
+  }
+
+  impl From<&SafeArguments> for QuoteConfig {
+    fn from(args: &SafeArguments) -> Self {
+      let mut config = QuoteConfig::new()
+        .unit(args.unit.clone())
+        .length(args.quote_length)
+        .min_length(args.min_length)
+        .separator(&args.separator)
+        .no_immediate_repeat(args.no_immediate_repeat)
+        .smart_start(args.smart_start)
+        .trace(args.trace)
+        .tokenizer(args.tokenizer.clone())
+        .no_append(args.no_append)
+        .no_capitalize(args.no_capitalize)
+        .stop_at(&args.stop_at.iter().map(String::as_str).collect::<Vec<&str>>())
+        .longest(args.longest)
+        .no_start_words(&args.no_start_words.iter().map(String::as_str).collect::<Vec<&str>>());
+      if let Some(seed) = args.seed {
+        config = config.seed(seed);
+      }
+      if let Some(max_chars) = args.max_chars {
+        config = config.max_chars(max_chars);
+      }
+      if let Some(ratio) = args.min_diversity {
+        config = config.min_diversity(ratio);
+      }
+      if let Some(max_quote_length) = args.max_quote_length {
+        config = config.length_range(args.quote_length, max_quote_length);
+      }
+      config
+    }
+  }
+
+  #[derive(Debug, PartialEq)]
+  pub struct Quote {
+    pub text: String,
+    pub length: usize,
+    pub timecode: Option<Duration>,
+    // the steps of the chain, when traced
+    pub trace: Vec<String>
+  }
+
+  impl fmt::Display for Quote {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+      write!(f, "{}", self.text)
+    }
+  }
+
+  pub fn get_quote(args: SafeArguments) -> Result<Quote, String> {
+    quote_from(&get_corpus(&args)?, &args)
+  }
+
+  // reading the subtitles and caches is kept apart from generating so that callers can tell
+  // unreadable inputs from a corpus unable to produce the requested quote
+  pub fn get_corpus(args: &SafeArguments) -> Result<MarkovDict, String> {
+    let cached = args.cache_file.as_ref()
+      .and_then(|cache_file| load_cache_file(args, cache_file, |cache_file| load_dict(cache_file.to_path_buf())));
+    if let Some(corpus) = cached {
+      return corpus_of(args, vec![corpus]);
+    }
+    build_corpus(args)
+  }
+
+  fn build_corpus(args: &SafeArguments) -> Result<MarkovDict, String> {
+    #[cfg(feature = "parallel")]
+    let dicts = args.subtitles.par_iter().map(|subtitle| get_dict(args, subtitle));
+    #[cfg(not(feature = "parallel"))]
+    let dicts = args.subtitles.iter().map(|subtitle| get_dict(args, subtitle));
+    corpus_of(args, save_cache_file(args, dicts.collect::<Result<Vec<MarkovDict>, String>>()?)?)
+  }
+
+  // same as `get_quote`, cache files being loaded through `dicts` rather than deserialized each time
+  pub fn get_quote_with(args: &SafeArguments, dicts: &mut DictCache) -> Result<Quote, String> {
+    let corpus = get_corpus_with(args, dicts)?;
+    quote_from(&corpus, args)
+  }
+
+  // a single dictionary is the one `dicts` holds, several are merged without copying them first
+  pub fn get_corpus_with(args: &SafeArguments, dicts: &mut DictCache) -> Result<Arc<MarkovDict>, String> {
+    if let Some(cache_file) = &args.cache_file {
+      return match load_cache_file(args, cache_file, |cache_file| dicts.load(cache_file)) {
+        Some(corpus) => shared_corpus_of(args, vec![corpus]),
+        None => build_corpus(args).map(Arc::new)
+      };
+    }
+    let mut loaded: Vec<Arc<MarkovDict>> = Vec::new();
+    for subtitle in args.subtitles.iter() {
+      let cached_dict = match args.no_cache {
+        true => None,
+        false => Some(cache_path(args, subtitle, &args.unit)?).filter(|cached_dict| cached_dict.is_file())
+      };
+      // missing or unreadable caches go through the usual path, which (re)writes them
+      let dict = match cached_dict.map(|cached_dict| dicts.load(&cached_dict)) {
+        Some(Ok(dict)) => dict,
+        _ => Arc::new(get_dict(args, subtitle)?)
+      };
+      loaded.push(dict);
+    }
+    shared_corpus_of(args, loaded)
+  }
+
+  fn shared_corpus_of(args: &SafeArguments, dicts: Vec<Arc<MarkovDict>>) -> Result<Arc<MarkovDict>, String> {
+    let corpus = match dicts.as_slice() {
+      [dict] => Arc::clone(dict),
+      dicts => {
+        let mut merged = MarkovDict::new();
+        for dict in dicts {
+          merged.merge(dict);
+        }
+        Arc::new(merged)
+      }
+    };
+    export_corpus(args, &corpus)?;
+    Ok(corpus)
+  }
+
+  // an unreadable cache file is rebuilt from the subtitles, like a subtitle's own cache
+  fn load_cache_file<T, F>(args: &SafeArguments, cache_file: &path::Path, load: F) -> Option<T>
+    where F: FnOnce(&path::Path) -> Result<T, String> {
+    if !cache_file.is_file() {
+      return None;
+    }
+    match timed(args, &format!("Loaded cache \"{}\"", cache_file.display()), || load(cache_file)) {
+      Ok(corpus) => Some(corpus),
+      Err(err) => {
+        if args.verbosity {
+          eprintln!("Rebuilding cache \"{}\": {}", cache_file.display(), err);
+        }
+        None
+      }
+    }
+  }
+
+  // subtitles aren't cached on their own with a cache file, their merged dictionary is saved there instead
+  fn save_cache_file(args: &SafeArguments, dicts: Vec<MarkovDict>) -> Result<Vec<MarkovDict>, String> {
+    match &args.cache_file {
+      Some(cache_file) => {
+        let corpus = merge_dicts(dicts);
+        timed(args, &format!("Saved cache \"{}\"", cache_file.display()), || save_dict(&corpus, cache_file, args.dict_format))?;
+        Ok(vec![corpus])
+      },
+      None => Ok(dicts)
+    }
+  }
+
+  fn corpus_of(args: &SafeArguments, dicts: Vec<MarkovDict>) -> Result<MarkovDict, String> {
+    let corpus = merge_dicts(dicts);
+    export_corpus(args, &corpus)?;
+    Ok(corpus)
+  }
+
+  fn export_corpus(args: &SafeArguments, corpus: &MarkovDict) -> Result<(), String> {
+    match &args.json_dict_out {
+      Some(json_dict_out) => export_dict(corpus, json_dict_out),
+      None => Ok(())
+    }
+  }
+
+  pub fn quote_from(dict: &MarkovDict, args: &SafeArguments) -> Result<Quote, String> {
+    if dict.is_empty() {
+      return Err(String::from("subtitle has too little dialogue to generate a quote"));
+    }
+    timed(args, "Generated the quote", || generate_quote(dict, args))
+  }
+
+  // returns the cache of each subtitle's main unit, the extra units' ones are built alongside
+  pub fn build_caches(args: SafeArguments) -> Result<Vec<PathBuf>, String> {
+    #[cfg(feature = "parallel")]
+    let built = args.subtitles.par_iter().map(|subtitle| get_dict(&args, subtitle));
+    #[cfg(not(feature = "parallel"))]
+    let built = args.subtitles.iter().map(|subtitle| get_dict(&args, subtitle));
+    let built = built.collect::<Result<Vec<MarkovDict>, String>>()?;
+    let (built, mut caches) = match &args.cache_file {
+      Some(cache_file) => (save_cache_file(&args, built)?, vec![cache_file.clone()]),
+      None => {
+        let caches = args.subtitles.iter()
+          .map(|subtitle| cache_path(&args, subtitle, &args.unit))
+          .collect::<Result<Vec<PathBuf>, String>>()?;
+        (built, caches)
+      }
+    };
+    // only building appends, so that generating from the corpus never adds the subtitles again
+    match &args.append_cache {
+      Some(corpus) => {
+        let appended = append_to_cache(corpus, merge_dicts(built), args.dict_format)?;
+        corpus_of(&args, vec![appended])?;
+        caches.push(corpus.clone());
+      },
+      None if args.json_dict_out.is_some() => {
+        corpus_of(&args, built)?;
+      },
+      None => ()
+    }
+    Ok(caches)
+  }
+
+  // unlike subtitle caches, an unreadable corpus is an error since it can't be rebuilt
+  fn append_to_cache(corpus: &path::Path, dict: MarkovDict, format: DictFormat) -> Result<MarkovDict, String> {
+    let dict = match corpus.is_file() {
+      true => {
+        let mut appended = load_dict(corpus.to_path_buf())
+          .map_err(|err| format!("{} (got \"{}\")", err, corpus.display()))?;
+        appended.merge(&dict);
+        appended
+      },
+      false => dict
+    };
+    save_dict(&dict, corpus, format)?;
+    Ok(dict)
+  }
+
+  // reports how long a stage took on stderr when verbose
+  fn timed<T, F: FnOnce() -> T>(args: &SafeArguments, stage: &str, run: F) -> T {
+    let started = Instant::now();
+    let result = run();
+    if args.verbosity {
+      eprintln!("{} in {:.2?}", stage, started.elapsed());
+    }
+    result
+  }
+
+  // each subtitle has its own cache, merged dictionaries are never cached
+  fn get_dict(args: &SafeArguments, subtitle_path: &path::Path) -> Result<MarkovDict, String> {
+    if args.no_cache || args.cache_file.is_some() {
+      let subtitle = read_dialogue(args, subtitle_path)?;
+      return Ok(build_args_dict(args, &subtitle, &args.unit));
+    }
+    let cached_dict = cache_path(args, subtitle_path, &args.unit)?;
+    let mut missing: Vec<(&Unit, PathBuf)> = Vec::new();
+    for unit in args.extra_units.iter() {
+      let cached_extra = cache_path(args, subtitle_path, unit)?;
+      if !cached_extra.is_file() {
+        missing.push((unit, cached_extra));
+      }
+    }
+    // an unreadable cache is rebuilt from the subtitle instead of failing the run
+    let cached = if cached_dict.is_file() {
+      let loaded = timed(args, &format!("Loaded cache \"{}\"", cached_dict.display()), || load_dict(cached_dict.clone()));
+      match loaded {
+        Ok(dict) => Some(dict),
+        Err(err) => {
+          if args.verbosity {
+            eprintln!("Rebuilding cache \"{}\": {}", cached_dict.display(), err);
+          }
+          None
+        }
+      }
+    } else {
+      None
+    };
+    let cached = match (cached, missing.is_empty()) {
+      (Some(dict), true) => return Ok(dict),
+      (cached, _) => cached
+    };
+    // the subtitle is read once and tokenized for every missing unit
+    let subtitle = read_dialogue(args, subtitle_path)?;
+    for (unit, cached_extra) in missing.iter() {
+      let dict = build_args_dict(args, &subtitle, unit);
+      timed(args, &format!("Saved cache \"{}\"", cached_extra.display()), || save_dict(&dict, cached_extra, args.dict_format))?;
+    }
+    match cached {
+      Some(dict) => Ok(dict),
+      None => {
+        let dict = build_args_dict(args, &subtitle, &args.unit);
+        timed(args, &format!("Saved cache \"{}\"", cached_dict.display()), || save_dict(&dict, &cached_dict, args.dict_format))?;
+        Ok(dict)
+      }
+    }
+  }
+
+  // a subtitle without any dialogue is reported as such, rather than caching an empty dictionary
+  // and failing later on for lack of keys
+  fn read_dialogue(args: &SafeArguments, subtitle_path: &path::Path) -> Result<Input, String> {
+    let subtitle = read_input(args, subtitle_path)?;
+    match subtitle.dialogue(&BuildOptions::default()).is_empty() {
+      true => Err(format!("{} (got \"{}\")", NO_DIALOGUE, subtitle_path.display())),
+      false => Ok(subtitle)
+    }
+  }
+
+  fn build_args_dict(args: &SafeArguments, subtitle: &Input, unit: &Unit) -> MarkovDict {
+    let options = BuildOptions::from(args);
+    let mut dict = timed(args, &format!("Built the {} dictionary", unit), || subtitle.build_dict(unit, &options));
+    if args.prune {
+      let pruned = prune_unreachable(&mut dict, prune_steps(args));
+      if args.verbosity {
+        eprintln!("Pruned {} unreachable keys", pruned);
+      }
+    }
+    dict
+  }
+
+  // every possible start is kept, so that excluding start words later on doesn't need a rebuild
+  pub fn prune_unreachable(dict: &mut MarkovDict, steps: usize) -> usize {
+    let starts: Vec<String> = dict.units_of(&starts(dict, &QuoteConfig::new()).0);
+    dict.prune(&starts.iter().map(String::as_str).collect::<Vec<&str>>(), steps)
+  }
+
+  // no unit of the longest requested quote is further from its start than that length
+  fn prune_steps(args: &SafeArguments) -> usize {
+    effective_length(args.max_quote_length.unwrap_or(args.quote_length).max(args.quote_length))
+  }
+
+  fn fnv_hash(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+      (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+  }
+
+  fn cache_path(args: &SafeArguments, subtitle_path: &path::Path, unit: &Unit) -> Result<PathBuf, String> {
+    let mut cached_dict = args.cache_directory.clone();
+    let subtitle = match subtitle_path.file_name() {
+      Some(filename) => path::Path::new(filename),
+      None => return Err(String::from("couldn't determine subtitle filename"))
+    };
+    // compressed subtitles share their cache with the uncompressed ones
+    let mut cache_name = if is_compressed(subtitle) {
+      match subtitle.file_stem() {
+        Some(stem) => stem.to_os_string(),
+        None => return Err(String::from("couldn't determine subtitle filename"))
+      }
+    } else {
+      subtitle.as_os_str().to_os_string()
+    };
+    // time ranges are cached apart from the whole subtitle, in seconds
+    if args.from_time.is_some() || args.to_time.is_some() {
+      let seconds = |time: Option<Duration>| time.map(|time| format!("{}s", time.as_secs())).unwrap_or_default();
+      cache_name.push(format!(".{}-{}", seconds(args.from_time), seconds(args.to_time)));
+    }
+    if !args.skip_cues.is_empty() {
+      let ranges: Vec<String> = args.skip_cues.iter()
+        .map(|(first, last)| match first == last {
+          true => first.to_string(),
+          false => format!("{}-{}", first, last)
+        })
+        .collect();
+      cache_name.push(format!(".skip-{}", ranges.join("_")));
+    }
+    // speakers are cached apart, their name being reduced to a safe file name
+    if let Some(speaker) = &args.speaker {
+      let safe: String = speaker.to_lowercase().chars().map(|c| if c.is_alphanumeric() { c } else { '_' }).collect();
+      cache_name.push(format!(".{}", safe));
+    }
+    // the subtitle's own extension is kept so that different formats never share a cache
+    cache_name.push(match (unit, args.keep_punctuation) {
+      (Unit::Word, false) => ".word",
+      (Unit::Grapheme, false) => ".char",
+      (Unit::Word, true) => ".word-punct",
+      (Unit::Grapheme, true) => ".char-punct",
+    });
+    // patterns are reduced to a hash (FNV-1a), being too long or unsafe for a file name
+    match (unit, &args.tokenizer) {
+      (Unit::Grapheme, _) | (_, Tokenizer::Whitespace) => (),
+      (_, Tokenizer::UnicodeWords) => cache_name.push("-unicode"),
+      (_, Tokenizer::Pattern(reg)) => cache_name.push(format!("-regex-{:016x}", fnv_hash(reg.as_str())))
+    }
+    // extraction commands may output anything from the same input
+    if let Some(cmd) = &args.extract_cmd {
+      cache_name.push(format!("-extract-{:016x}", fnv_hash(cmd)));
+    }
+    // the same bytes are other units in another encoding
+    match args.encoding {
+      Some(encoding) if encoding != UTF_8 => cache_name.push(format!("-encoding-{}", encoding.name().to_lowercase())),
+      _ => ()
+    }
+    // a forced format reads the same file into other cues
+    if let Some(format) = args.format {
+      cache_name.push(format!("-format-{}", format));
+    }
+    if args.drop_spaces && *unit == Unit::Grapheme {
+      cache_name.push("-nospaces");
+    }
+    if args.sentence_boundaries {
+      cache_name.push("-sentences");
+    }
+    if args.cue_boundaries {
+      cache_name.push("-cues");
+    }
+    if args.keep_duplicates {
+      cache_name.push("-duplicates");
+    }
+    if args.keep_captions {
+      cache_name.push("-captions");
+    }
+    // pruned dictionaries only hold for the length they were pruned for
+    if args.prune {
+      cache_name.push(format!("-pruned-{}", prune_steps(args)));
+    }
+    if args.dict_format == DictFormat::Binary {
+      cache_name.push(".bin");
+    }
+    cached_dict.push(cache_name);
+    if cached_dict.exists() && !cached_dict.is_file() {
+      return Err(format!(
+        "cache path \"{}\" exists but isn't a file (remove it or use another cache directory)",
+        cached_dict.display()
+      ));
+    }
+    Ok(cached_dict)
+  }
+
+  pub fn get_dialogue(args: SafeArguments) -> Result<String, String> {
+    let options = BuildOptions::from(&args);
+    let mut dialogue: Vec<String> = Vec::new();
+    for subtitle_path in args.subtitles.iter() {
+      let subtitle = read_input(&args, subtitle_path)?;
+      dialogue.extend(subtitle.dialogue(&options));
+    }
+    Ok(dialogue.join("\n"))
+  }
+
+  #[derive(Debug, PartialEq)]
+  pub struct CorpusStats {
+    pub cues: usize,
+    pub lines: usize,
+    pub keys: usize,
+    pub starters: usize,
+    pub transitions: usize,
+    pub variety: Option<Variety>
+  }
+
+  // a rough order of magnitude only: every start is assumed to be followed by as many branches as the average unit
+  #[derive(Debug, PartialEq)]
+  pub struct Variety {
+    pub starts: usize,
+    pub branching: f64,
+    pub length: i32,
+    pub log10_quotes: f64
+  }
+
+  impl fmt::Display for Variety {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+      write!(
+        f,
+        "{} starts, {:.2} successors per key (geometric mean), about 10^{:.1} quotes of {} units",
+        self.starts,
+        self.branching,
+        self.log10_quotes,
+        self.length
+      )
+    }
+  }
+
+  // units without successors end the chain and don't count towards the branching factor; endless quotes are
+  // estimated at the default length
+  pub fn estimate_variety(dict: &MarkovDict, config: &QuoteConfig) -> Variety {
+    let starts = starts(dict, config).0.len();
+    let counts: Vec<f64> = dict.key_ids()
+      .map(|id| dict.successors[id as usize].iter().collect::<HashSet<&u32>>().len() as f64)
+      .collect();
+    let branching = match counts.is_empty() {
+      true => 0.0,
+      false => (counts.iter().map(|count| count.ln()).sum::<f64>() / counts.len() as f64).exp()
+    };
+    let length = match config.quote_length() {
+      0 => UnsafeArguments::get_default_quote_length(Some(config.unit.clone())),
+      length => length
+    };
+    let log10_quotes = match starts {
+      0 => 0.0,
+      _ => (starts as f64).log10() + f64::from(length.saturating_sub(1)) * branching.log10()
+    };
+    Variety { starts, branching, length, log10_quotes }
+  }
+
+  impl fmt::Display for CorpusStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+      write!(
+        f,
+        "{} cues, {} dialogue lines, {} keys, {} starters, {} transitions",
+        self.cues,
+        self.lines,
+        self.keys,
+        self.starters,
+        self.transitions
+      )?;
+      match &self.variety {
+        Some(variety) => write!(f, "; {}", variety),
+        None => Ok(())
+      }
+    }
+  }
+
+  // nothing is cached, the subtitle is only read, turned into SubRip as `stats` does, and tokenized
+  pub fn inspect_subtitle(subtitle_path: &path::Path, unit: &Unit) -> Result<CorpusStats, String> {
+    let content = read_subtitle(subtitle_path, is_compressed(subtitle_path), None)?;
+    let format = sniff_format(subtitle_path, &content);
+    let subtitle = to_input(content, format);
+    let options = BuildOptions::default();
+    Ok(corpus_stats(&subtitle, subtitle.build_dict(unit, &options), &options, None))
+  }
+
+  // what the verification of a cache found; an outdated cache is still used, its quotes having no timecode
+  #[derive(Clone, Debug, PartialEq)]
+  pub enum CacheStatus {
+    Healthy,
+    Outdated,
+    Broken(String)
+  }
+
+  impl fmt::Display for CacheStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+      match self {
+        CacheStatus::Healthy => write!(f, "healthy cache"),
+        CacheStatus::Outdated => write!(f, "outdated cache written before timecodes were recorded"),
+        CacheStatus::Broken(err) => write!(f, "{}", err)
+      }
+    }
+  }
+
+  // every file of the cache directory along with its status; nothing is written
+  pub fn verify_caches(args: &SafeArguments) -> Result<Vec<(PathBuf, CacheStatus)>, String> {
+    let entries = fs::read_dir(&args.cache_directory).map_err(|err| {
+      format!("couldn't read cache directory \"{}\": {}", args.cache_directory.display(), err)
+    })?;
+    let mut caches: Vec<PathBuf> = entries
+      .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+      .filter(|path| path.is_file())
+      .collect();
+    caches.sort();
+    Ok(caches.into_iter().map(|cache| {
+      let status = verify_cache(&cache);
+      (cache, status)
+    }).collect())
+  }
+
+  fn verify_cache(cache: &path::Path) -> CacheStatus {
+    let dict = match load_dict(cache.to_path_buf()) {
+      Ok(dict) => dict,
+      Err(err) => return CacheStatus::Broken(err)
+    };
+    // every key followed by something has had a timecode since they were recorded
+    match !dict.is_empty() && dict.keys().iter().all(|key| dict.timecode(key).is_none()) {
+      true => CacheStatus::Outdated,
+      false => CacheStatus::Healthy
+    }
+  }
+
+  pub fn get_stats(args: &SafeArguments) -> Result<Vec<(PathBuf, CorpusStats)>, String> {
+    let options = BuildOptions::from(args);
+    let mut stats: Vec<(PathBuf, CorpusStats)> = Vec::new();
+    for subtitle_path in args.subtitles.iter() {
+      let subtitle = read_input(args, subtitle_path)?;
+      let dict = build_args_dict(args, &subtitle, &args.unit);
+      let config = match args.estimate_variety {
+        true => Some(QuoteConfig::from(args)),
+        false => None
+      };
+      stats.push((subtitle_path.clone(), corpus_stats(&subtitle, dict, &options, config)));
+    }
+    Ok(stats)
+  }
+
+  fn corpus_stats(subtitle: &Input, dict: MarkovDict, options: &BuildOptions, variety: Option<QuoteConfig>) -> CorpusStats {
+    CorpusStats {
+      cues: subtitle.cues().iter().filter(|cue| options.uses(cue)).count(),
+      lines: subtitle.dialogue(options).len(),
+      keys: dict.len(),
+      starters: starts(&dict, &QuoteConfig::new()).0.len(),
+      transitions: dict.transitions(),
+      variety: variety.map(|config| estimate_variety(&dict, &config))
+    }
+  }
+
+  // tolerates CRLF line endings, byte order marks, missing blank lines between cues, dots, missing hours or
+  // milliseconds in timecodes and trailing cue settings, and never keeps a timing line as dialogue
+  fn parse_subrip(subtitle: &str) -> Vec<Cue<'_>> {
+    let index_reg = Regex::new(r"^\d+$").unwrap();
+    let timecode_reg = Regex::new(
+      r"^(?:(\d+):)?(\d{1,2}):(\d{1,2})(?:[,.:](\d{1,3}))?\s*-->\s*(?:\d+:)?\d{1,2}:\d{1,2}(?:[,.:]\d{1,3})?(?:\s.*)?$"
+    ).unwrap();
+    // only the start of a cue is kept, the digits are guaranteed by the regex but may overflow
+    let start = |line: &str| -> Option<Duration> {
+      let caps = timecode_reg.captures(line)?;
+      let part = |index: usize| caps.get(index).map_or(Some(0), |part| part.as_str().parse::<u64>().ok());
+      let millis = match caps.get(4) {
+        Some(millis) => format!("{:0<3}", millis.as_str()).parse::<u64>().ok()?,
+        None => 0
+      };
+      let seconds = part(1)?.checked_mul(3600)?.checked_add(part(2)? * 60 + part(3)?)?;
+      Some(Duration::from_millis(seconds.checked_mul(1000)?.checked_add(millis)?))
+    };
+    // cues are numbered by their index line, or by their position when it's missing or unparsable
+    let mut cues: Vec<Cue> = Vec::new();
+    let mut index: Option<u64> = None;
+    let mut state = SubripState::Index;
+    for line in subtitle.lines() {
+      let line = line.trim_matches(|c: char| c.is_whitespace() || c == '\u{feff}');
+      let position = cues.len() as u64 + 1;
+      state = match state {
+        SubripState::Index => {
+          if index_reg.is_match(line) {
+            index = line.parse().ok();
+            SubripState::Timecode
+          } else if let Some(start) = start(line) {
+            cues.push(Cue::new(position, start));
+            SubripState::Text
+          } else {
+            SubripState::Index
+          }
+        },
+        SubripState::Timecode => {
+          if let Some(start) = start(line) {
+            cues.push(Cue::new(index.take().unwrap_or(position), start));
+            SubripState::Text
+          } else {
+            index = None;
+            SubripState::Index
+          }
+        },
+        SubripState::Text => {
+          if line.is_empty() {
+            SubripState::Index
+          } else if let Some(start) = start(line) {
+            // the previous cue wasn't closed by a blank line, its last line being the new cue's index
+            if let Some(cue) = cues.last_mut() {
+              if cue.lines.last().is_some_and(|last| index_reg.is_match(last)) {
+                index = cue.lines.pop().and_then(|last| last.parse().ok());
+              }
+            }
+            cues.push(Cue::new(index.take().unwrap_or(position), start));
+            SubripState::Text
+          } else {
+            // unparsable timing lines are dropped rather than taken for dialogue
+            if !line.contains("-->") {
+              if let Some(cue) = cues.last_mut() {
+                cue.lines.push(line);
+              }
+            }
+            SubripState::Text
+          }
+        }
+      };
+    }
+    cues
+  }
+
+  // matches quotes, tags and speaker labels (a bracket starting a line being one), along with captions
+  // unless they're kept: each bracket, parenthesis or pair of music symbols is matched on its own so
+  // that the dialogue around it stays
+  fn noise_regex(keep_captions: bool) -> Regex {
+    let noise = r#""\s?|<[^>]*>|^-?\s?[a-zA-Z]+:\s?|^\[[^\]]*\]\s?|^\([^)]*\)\s?"#;
+    match keep_captions {
+      true => Regex::new(noise).unwrap(),
+      false => Regex::new(&format!(r"{}|\[[^\]]*\]|\([^)]*\)|♪[^♪]*♪?|#\s[^#]*#?", noise)).unwrap()
+    }
+  }
+
+  // the removed noise leaves its surrounding spaces behind
+  fn clean_line(line: &str, noise_reg: Option<&Regex>) -> String {
+    let cleaned = noise_reg.map_or(Cow::from(line), |noise_reg| noise_reg.replace_all(line, ""));
+    cleaned.split_whitespace().collect::<Vec<&str>>().join(" ")
+  }
+
+  pub fn dialogue_lines(subtitle: &str) -> Vec<String> {
+    let options = BuildOptions::default();
+    cues_dialogue(&parse_subrip(subtitle), Some(&noise_regex(options.keep_captions)), &options)
+  }
+
+  fn cues_dialogue(cues: &[Cue], noise_reg: Option<&Regex>, options: &BuildOptions) -> Vec<String> {
+    let speaker = options.speaker.as_deref();
+    let speaker_reg = speaker.map(|_| speaker_regex());
+    cues.iter()
+      .filter(|cue| options.uses(cue))
+      .flat_map(|cue| cue_lines(cue, speaker.zip(speaker_reg.as_ref())))
+      .map(|line| clean_line(line, noise_reg))
+      .filter(|line| !line.is_empty())
+      .collect()
+  }
+
+  // how a subtitle is turned into a dictionary, the defaults being those of the command line
+  #[derive(Clone, Debug, Default)]
+  pub struct BuildOptions {
+    pub keep_punctuation: bool,
+    pub sentence_boundaries: bool,
+    pub cue_boundaries: bool,
+    // only cues starting within the (inclusive) time range are used
+    pub from_time: Option<Duration>,
+    pub to_time: Option<Duration>,
+    pub speaker: Option<String>,
+    pub tokenizer: Tokenizer,
+    pub skip_cues: Vec<(u64, u64)>,
+    pub keep_duplicates: bool,
+    pub keep_captions: bool,
+    pub drop_spaces: bool
+  }
+
+  impl BuildOptions {
+    fn uses(&self, cue: &Cue) -> bool {
+      cue.within(self.from_time, self.to_time) && !cue.skipped(&self.skip_cues)
+    }
+  }
+
+  impl From<&SafeArguments> for BuildOptions {
+    fn from(args: &SafeArguments) -> Self {
+      BuildOptions {
+        keep_punctuation: args.keep_punctuation,
+        sentence_boundaries: args.sentence_boundaries,
+        cue_boundaries: args.cue_boundaries,
+        from_time: args.from_time,
+        to_time: args.to_time,
+        speaker: args.speaker.clone(),
+        tokenizer: args.tokenizer.clone(),
+        skip_cues: args.skip_cues.clone(),
+        keep_duplicates: args.keep_duplicates,
+        keep_captions: args.keep_captions,
+        drop_spaces: args.drop_spaces
+      }
+    }
+  }
+
+  pub fn build_dict(subtitle: &str, unit: &Unit, keep_punctuation: bool) -> MarkovDict {
+    build_dict_between(subtitle, unit, &BuildOptions { keep_punctuation, ..BuildOptions::default() })
+  }
+
+  pub fn build_dict_between(subtitle: &str, unit: &Unit, options: &BuildOptions) -> MarkovDict {
+    build_cues_dict(&parse_subrip(subtitle), Some(&noise_regex(options.keep_captions)), unit, options)
+  }
+
+  // each line of the text is a cue of its own, said at its very start
+  pub fn build_dict_from_text(text: &str, unit: &Unit, options: &BuildOptions) -> MarkovDict {
+    build_cues_dict(&text_cues(text), None, unit, options)
+  }
+
+  fn build_cues_dict(cues: &[Cue], noise_reg: Option<&Regex>, unit: &Unit, options: &BuildOptions) -> MarkovDict {
+    let BuildOptions {
+      keep_punctuation, sentence_boundaries, cue_boundaries, keep_duplicates, drop_spaces, ..
+    } = *options;
+    let tokenizer = &options.tokenizer;
+    let speaker = options.speaker.as_deref();
+    let mut dict = MarkovDict::new();
+    // the regexes of options left out aren't compiled, this being called once per subtitle and unit
+    let speaker_reg = speaker.map(|_| speaker_regex());
+    let sentence_reg = match sentence_boundaries {
+      true => Some(sentence_regex()),
+      false => None
+    };
+    let punct_reg = match keep_punctuation {
+      true => None,
+      false => Some(punct_regex())
+    };
+    let word_punct_reg = match (unit, tokenizer, keep_punctuation) {
+      (Unit::Word, Tokenizer::Whitespace, true) => Some(word_punct_regex()),
+      _ => None
+    };
+    let cue_lines: Vec<(u64, Duration, String)> = cues.iter()
+      .filter(|cue| options.uses(cue))
+      .flat_map(|cue| {
+        let lines = cue_lines(cue, speaker.zip(speaker_reg.as_ref()));
+        cue_sentences(&lines, noise_reg).into_iter().map(move |sentence| (cue.index, cue.start, sentence))
+      })
+      .collect();
+    // a line lingering on screen over several cues is only said once, at its first cue, while a cue
+    // saying a line twice keeps both
+    let lines: Vec<(Duration, String)> = cue_lines.iter()
+      .enumerate()
+      .filter(|(index, (cue, _, line))| match index.checked_sub(1).map(|previous| &cue_lines[previous]) {
+        Some((previous_cue, _, previous_line)) => keep_duplicates || previous_cue == cue || previous_line != line,
+        None => true
+      })
+      .map(|(_, (_, start, line))| (*start, line.clone()))
+      .collect();
+    // each sentence goes along with whether it starts its cue's dialogue
+    let sentences = lines.into_iter()
+      .flat_map(|(start, sentence)| match &sentence_reg {
+        Some(sentence_reg) => sentence_reg.find_iter(&sentence)
+          .enumerate()
+          .map(|(index, part)| (start, String::from(part.as_str()), index == 0))
+          .collect(),
+        None => vec![(start, sentence, true)]
+      });
+    for (start, sentence, opens_cue) in sentences {
+      let replaced = match &punct_reg {
+        Some(punct_reg) => punct_reg.replace_all(&sentence, ""),
+        None => Cow::from(sentence.as_str())
+      };
+      let mut units = split_units(&replaced, unit, keep_punctuation, tokenizer, word_punct_reg.as_ref());
+      // only graphemes can be whitespace
+      if drop_spaces {
+        units.retain(|unit| !unit.trim().is_empty());
+      }
+      if (sentence_boundaries || (cue_boundaries && opens_cue)) && units.len() > 1 {
+        dict.add_starter(units[0]);
+      }
+      for pair in units.windows(2) {
+        dict.add_transition(pair[0], pair[1]);
+        dict.add_timecode(pair[0], start);
+      }
+    }
+    dict.sort();
+    dict
+  }
+
+  // the lines of a cue usually are one sentence broken in two, they are joined so that the chain
+  // goes over the break, unless a dash starts the next speaker's line
+  fn cue_sentences(lines: &[&str], noise_reg: Option<&Regex>) -> Vec<String> {
+    let mut sentences: Vec<String> = Vec::new();
+    for line in lines.iter() {
+      let cleaned = clean_line(line, noise_reg);
+      match sentences.last_mut() {
+        Some(sentence) if !cleaned.starts_with('-') => {
+          if !cleaned.is_empty() {
+            sentence.push(' ');
+            sentence.push_str(&cleaned);
+          }
+        },
+        _ => sentences.push(cleaned)
+      }
+    }
+    sentences
+  }
+
+  // a label starting a line names its speaker, who goes on speaking until the cue ends or another
+  // label or a dash starts a line
+  fn cue_lines<'a>(cue: &Cue<'a>, speaker: Option<(&str, &Regex)>) -> Vec<&'a str> {
+    let (speaker, speaker_reg) = match speaker {
+      Some((speaker, speaker_reg)) => (speaker.to_lowercase(), speaker_reg),
+      None => return cue.lines.clone()
+    };
+    let mut current: Option<String> = None;
+    let mut lines: Vec<&str> = Vec::new();
+    for line in cue.lines.iter() {
+      match speaker_reg.captures(line) {
+        Some(caps) => current = caps.iter().skip(1).flatten().next().map(|name| name.as_str().trim().to_lowercase()),
+        None if line.starts_with('-') => current = None,
+        None => ()
+      }
+      if current.as_deref() == Some(speaker.as_str()) {
+        lines.push(line);
+      }
+    }
+    lines
+  }
+
+  fn speaker_regex() -> Regex {
+    Regex::new(r"^-?\s*(?:\[([^\]]+)\]|\(([^)]+)\)|<v(?:\.[^\s>]*)?\s+([^>]+)>|([A-Za-z][\w .'-]*):\s)").unwrap()
+  }
+
+  // a sentence goes up to its closing marks, the last one possibly having none
+  fn sentence_regex() -> Regex {
+    Regex::new(r"[^.?!…]*[^.?!…\s][^.?!…]*[.?!…]*").unwrap()
+  }
+
+  fn punct_regex() -> Regex {
+    Regex::new(r",|-|\.|;|\?|!").unwrap()
+  }
+
+  fn word_punct_regex() -> Regex {
+    Regex::new(r"[\w'’]+|[^\w\s]").unwrap()
+  }
+
+  fn split_units<'a>(
+    line: &'a str,
+    unit: &Unit,
+    keep_punctuation: bool,
+    tokenizer: &Tokenizer,
+    word_punct_reg: Option<&Regex>
+  ) -> Vec<&'a str> {
+    match (unit, tokenizer, keep_punctuation) {
+      (Unit::Word, Tokenizer::Whitespace, false) => line.split_whitespace().collect(),
+      // compiled here unless the caller splits many lines
+      (Unit::Word, Tokenizer::Whitespace, true) => match word_punct_reg {
+        Some(word_punct_reg) => word_punct_reg.find_iter(line).map(|unit| unit.as_str()).collect(),
+        None => word_punct_regex().find_iter(line).map(|unit| unit.as_str()).collect()
+      },
+      (Unit::Word, Tokenizer::UnicodeWords, false) => line.unicode_words().collect(),
+      // word boundaries also delimit punctuation, only the whitespace between words is dropped
+      (Unit::Word, Tokenizer::UnicodeWords, true) => {
+        line.split_word_bounds().filter(|unit| !unit.trim().is_empty()).collect()
+      },
+      (Unit::Word, Tokenizer::Pattern(reg), _) => {
+        reg.split(line).map(str::trim).filter(|unit| !unit.is_empty()).collect()
+      },
+      (Unit::Grapheme, _, _) => line.trim().graphemes(true).collect()
+    }
+  }
+
+  pub fn merge_dicts(dicts: Vec<MarkovDict>) -> MarkovDict {
+    let mut dicts = dicts.into_iter();
+    let mut merged = dicts.next().unwrap_or_default();
+    for dict in dicts {
+      merged.merge(&dict);
+    }
+    merged
+  }
+
+  pub fn reverse_dict(dict: &MarkovDict) -> MarkovDict {
+    dict.reverse()
+  }
+
+  fn is_compressed(subtitle: &path::Path) -> bool {
+    subtitle.extension() == Some(OsStr::new("gz"))
+  }
+
+  // every format but plain text is turned into SubRip, which the dictionaries are built from
+  fn read_input(args: &SafeArguments, subtitle: &path::Path) -> Result<Input, String> {
+    let content = match &args.extract_cmd {
+      Some(cmd) => extract_subtitle(cmd, subtitle, args.encoding)?,
+      None => read_subtitle(subtitle, is_compressed(subtitle), args.encoding)?
+    };
+    // extraction commands print SubRip whatever the input file is
+    let format = match (args.format, &args.extract_cmd) {
+      (Some(format), _) => format,
+      (None, Some(_)) => sniff_content(&content),
+      (None, None) => sniff_format(subtitle, &content)
+    };
+    if args.verbosity {
+      eprintln!("Reading {} as {}", subtitle.display(), format);
+    }
+    Ok(to_input(content, format))
+  }
+
+  // the extension is trusted first, the uncompressed one for gzipped subtitles
+  pub fn sniff_format(subtitle_path: &path::Path, subtitle: &str) -> Format {
+    let name = match is_compressed(subtitle_path) {
+      true => subtitle_path.file_stem().map(path::Path::new),
+      false => Some(subtitle_path)
+    };
+    let extension = name.and_then(path::Path::extension).map(|extension| extension.to_string_lossy().to_lowercase());
+    match extension.as_deref() {
+      Some("srt") => Format::Srt,
+      Some("vtt") => Format::Vtt,
+      Some("ass") | Some("ssa") => Format::Ass,
+      _ => sniff_content(subtitle)
+    }
+  }
+
+  fn sniff_content(subtitle: &str) -> Format {
+    let timecode_reg = Regex::new(r"^(\d+:)?\d{1,2}:\d{1,2}([,.:]\d{1,3})?\s*-->").unwrap();
+    let lines: Vec<&str> = subtitle.lines()
+      .map(|line| line.trim_matches(|c: char| c.is_whitespace() || c == '\u{feff}'))
+      .filter(|line| !line.is_empty())
+      .take(20)
+      .collect();
+    if lines.first().is_some_and(|line| line.starts_with("WEBVTT")) {
+      Format::Vtt
+    } else if lines.iter().any(|line| line.eq_ignore_ascii_case("[Script Info]") || line.starts_with("Dialogue:")) {
+      Format::Ass
+    } else if lines.iter().any(|line| timecode_reg.is_match(line)) {
+      Format::Srt
+    } else {
+      Format::Text
+    }
+  }
+
+  // WebVTT cues are read as is by the SubRip parser, which skips its header and blocks without timing
+  fn to_input(subtitle: String, format: Format) -> Input {
+    let cue = |index: usize, start: Duration, text: &str| {
+      let millis = start.as_millis();
+      format!(
+        "{}\n{:02}:{:02}:{:02},{:03} --> {:02}:{:02}:{:02},{:03}\n{}\n",
+        index + 1,
+        millis / 3_600_000, millis / 60_000 % 60, millis / 1000 % 60, millis % 1000,
+        millis / 3_600_000, millis / 60_000 % 60, millis / 1000 % 60, millis % 1000,
+        text
+      )
+    };
+    match format {
+      Format::Srt | Format::Vtt => Input::Subrip(subtitle),
+      Format::Text => Input::Text(subtitle),
+      Format::Ass => {
+        let override_reg = Regex::new(r"\{[^}]*\}").unwrap();
+        let time_reg = Regex::new(r"^(\d+):(\d{1,2}):(\d{1,2})[.,](\d{1,2})$").unwrap();
+        let subrip = subtitle.lines()
+          .filter_map(|line| line.trim().strip_prefix("Dialogue:"))
+          .filter_map(|dialogue| {
+            // Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, then the text
+            let fields: Vec<&str> = dialogue.splitn(10, ',').collect();
+            let caps = time_reg.captures(fields.get(1)?.trim())?;
+            let part = |index: usize| caps[index].parse::<u64>().ok();
+            let seconds = part(1)?.checked_mul(3600)?.checked_add(part(2)? * 60 + part(3)?)?;
+            let start = Duration::from_millis(seconds.checked_mul(1000)?.checked_add(part(4)? * 10)?);
+            let text = override_reg.replace_all(fields.get(9)?, "").replace("\\N", "\n").replace("\\n", "\n");
+            // the speaker is kept as a label, which is noise unless a speaker is selected
+            match fields.get(4).map(|name| name.trim()).filter(|name| !name.is_empty()) {
+              Some(name) => Some((start, format!("[{}] {}", name, text.trim()))),
+              None => Some((start, text))
             }
-          },
-          Err(err) => Err(err)
-        }
+          })
+          .filter(|(_, text)| !text.trim().is_empty())
+          .enumerate()
+          .map(|(index, (start, text))| cue(index, start, text.trim()))
+          .collect::<Vec<String>>()
+          .join("\n");
+        Input::Subrip(subrip)
+      }
+    }
+  }
+
+  // plain text goes straight to its lines, without the SubRip parser nor the noise it cleans up
+  enum Input {
+    Subrip(String),
+    Text(String)
+  }
+
+  impl Input {
+    fn cues(&self) -> Vec<Cue<'_>> {
+      match self {
+        Input::Subrip(subtitle) => parse_subrip(subtitle),
+        Input::Text(text) => text_cues(text)
+      }
+    }
+    fn noise_regex(&self, keep_captions: bool) -> Option<Regex> {
+      match self {
+        Input::Subrip(_) => Some(noise_regex(keep_captions)),
+        Input::Text(_) => None
+      }
+    }
+    fn dialogue(&self, options: &BuildOptions) -> Vec<String> {
+      cues_dialogue(&self.cues(), self.noise_regex(options.keep_captions).as_ref(), options)
+    }
+    fn build_dict(&self, unit: &Unit, options: &BuildOptions) -> MarkovDict {
+      build_cues_dict(&self.cues(), self.noise_regex(options.keep_captions).as_ref(), unit, options)
+    }
+  }
+
+  fn text_cues(text: &str) -> Vec<Cue<'_>> {
+    text.lines()
+      .map(str::trim)
+      .filter(|line| !line.is_empty())
+      .enumerate()
+      .map(|(index, line)| Cue { lines: vec![line], ..Cue::new(index as u64 + 1, Duration::default()) })
+      .collect()
+  }
+
+  fn read_subtitle(subtitle: &path::Path, compressed: bool, encoding: Option<&'static Encoding>) -> Result<String, String> {
+    let content = if !compressed {
+      match fs::read(subtitle) {
+        Ok(content) => content,
+        Err(err) => return Err(format!("couldn't open subtitle file: {}", err))
+      }
+    } else {
+      let file = match fs::File::open(subtitle) {
+        Ok(file) => file,
+        Err(err) => return Err(format!("couldn't open subtitle file: {}", err))
+      };
+      let mut content = Vec::new();
+      match GzDecoder::new(file).read_to_end(&mut content) {
+        Ok(_) => content,
+        Err(err) => return Err(format!("couldn't decompress subtitle file: {}", err))
       }
+    };
+    // decoding is kept apart from reading so that invalid text isn't reported as an unreadable file
+    decode_subtitle(content, encoding).map_err(|offset| format!(
+      "subtitle file \"{}\" isn't valid UTF-8 (invalid byte at offset {}), use --encoding to read it as another encoding",
+      subtitle.display(),
+      offset
+    ))
+  }
+
+  // UTF-8 is decoded strictly, other encodings can't fail; the error is the first invalid byte's offset
+  fn decode_subtitle(content: Vec<u8>, encoding: Option<&'static Encoding>) -> Result<String, usize> {
+    match encoding {
+      Some(encoding) if encoding != UTF_8 => Ok(encoding.decode_without_bom_handling(&content).0.into_owned()),
+      _ => String::from_utf8(content).map_err(|err| err.utf8_error().valid_up_to())
+    }
+  }
+
+  fn extract_subtitle(cmd: &str, input: &path::Path, encoding: Option<&'static Encoding>) -> Result<String, String> {
+    // the input is handed over as a positional parameter so it never needs quoting
+    let output = match Command::new("sh")
+      .arg("-c")
+      .arg(cmd.replace("{}", "\"$1\""))
+      .arg("sh")
+      .arg(input)
+      .output() {
+      Ok(output) => output,
+      Err(err) => return Err(format!("couldn't run extraction command: {}", err))
+    };
+    if !output.status.success() {
+      let stderr = String::from_utf8_lossy(&output.stderr);
+      return Err(match stderr.trim() {
+        "" => format!("extraction command failed ({})", output.status),
+        err => format!("extraction command failed ({}): {}", output.status, err)
+      });
+    }
+    match decode_subtitle(output.stdout, encoding) {
+      Ok(content) => Ok(content),
+      Err(_) => Err(String::from("extraction command didn't output valid UTF-8"))
     }
   }
 
-  fn load_dict(cached_dict: path::PathBuf) -> Result<HashMap<String, Vec<String>>, String> {
+  // either format is read, JSON caches being the ones opening on "{"
+  fn load_dict(cached_dict: path::PathBuf) -> Result<MarkovDict, String> {
     let ser_dict = match fs::File::open(cached_dict) {
       Ok(file) => file,
       Err(_) => return Err(String::from("couldn't open cached file"))
     };
-    let reader = BufReader::new(ser_dict);
-    let de_dict: Entries = match serde_json::from_reader(reader) {
-      Ok(dict) => dict,
-      Err(_) => return Err(String::from("couldn't deserialize cached file"))
+    let mut reader = BufReader::new(ser_dict);
+    let json = match reader.fill_buf() {
+      Ok(bytes) => bytes.first().copied().unwrap_or(b'{') == b'{',
+      Err(_) => return Err(String::from("couldn't open cached file"))
     };
-    let mut dict: HashMap<String, Vec<String>> = HashMap::new();
-    for entry in de_dict.entries.iter() {
-      match dict.insert(entry.key.clone(), entry.pairs.clone()) {
-        _ => ()
-      }
+    match json {
+      true => MarkovDict::read_from(reader),
+      false => MarkovDict::read_binary(reader)
     }
-    Ok(dict)
   }
 
-  fn save_dict(mut dict: HashMap<String, Vec<String>>, file_path: &path::PathBuf) -> Result<(), String> {
-    let mut entries = Entries::new();
-    dict.drain().for_each(|(key, d_entry)| {
-      entries.add_entry(Entry::new(key, d_entry))
-    });
+  fn save_dict(dict: &MarkovDict, file_path: &path::Path, format: DictFormat) -> Result<(), String> {
     let output = match fs::File::create(file_path) {
       Ok(file) => file,
-      Err(_) => return Err(String::from("couldn't create cache file"))
+      Err(err) => return Err(format!("couldn't create cache file \"{}\": {}", file_path.display(), err))
     };
-    match serde_json::to_writer(output, &entries) {
-      Ok(_) => Ok(()),
-      Err(_) => return Err(String::from("couldn't write to cache file"))
+    let mut writer = BufWriter::new(output);
+    match format {
+      DictFormat::Json => dict.write_to(&mut writer)?,
+      DictFormat::Binary => dict.write_binary(&mut writer)?
     }
+    writer.flush().map_err(|_| String::from("couldn't write to cache file"))
   }
 
-  fn generate_quote(dict: &HashMap<String, Vec<String>>, quote_length: i32) -> Result<String, String> {
-    let mut starts = dict.keys().filter(|key| {
-      let v: Vec<char> = key.chars().collect();
-        if v[0].is_uppercase() {
-          return true;
-        } else {
-          return false;
+  fn export_dict(dict: &MarkovDict, file_path: &path::Path) -> Result<(), String> {
+    let output = match fs::File::create(file_path) {
+      Ok(file) => file,
+      Err(err) => return Err(format!("couldn't create dictionary export \"{}\": {}", file_path.display(), err))
+    };
+    let mut writer = BufWriter::new(output);
+    dict.write_json(&mut writer)?;
+    writer.flush().map_err(|_| String::from("couldn't write to dictionary export"))
+  }
+
+  fn generate_quote(dict: &MarkovDict, args: &SafeArguments) -> Result<Quote, String> {
+    let config = QuoteConfig::from(args);
+    let mut rng = config.rng();
+    let mut generation = Generation::new(dict, &config, None);
+    // units of the continued phrase don't count towards the requested length
+    let (branch, kept) = match (&args.continue_phrase, &args.must_include, &args.end_word) {
+      (_, _, Some(word)) => {
+        let mut branch = ending_branch(dict, &reverse_dict(dict), word, &config, &mut rng)?;
+        if args.verbosity {
+          eprintln!("Ending on {}: {}", args.unit, branch[branch.len() - 1]);
         }
-    });
-    let count = starts.clone().count();
-    if count > 0 {
-      let random = rand::thread_rng().gen_range(0, starts.clone().count());
-      let first = match starts.nth(random) {
-        Some(entry) => entry.clone(),
-        None => return Err(String::from("couldn't determine the quote starting point"))
-      };
-      let branch = build_branch(&dict, first, quote_length);
-      let mut quote = match branch.to_vec() {
-        Some(vec_quote) => vec_quote.join(" "),
-        None => return Err(String::from("couldn't build a random quote"))
+        check_ending_branch(&mut branch, &config)?;
+        (branch, 0)
+      },
+      (_, Some(word), None) => {
+        let (mut branch, index) = around_branch(dict, &reverse_dict(dict), word, &config, &mut rng)?;
+        if args.verbosity {
+          eprintln!("Including {}: {}", args.unit, branch[index]);
+        }
+        check_around_branch(&mut branch, index, &config)?;
+        (branch, 0)
+      },
+      (Some(phrase), None, None) => {
+        let (mut branch, last) = continue_branch(dict, phrase, &config, args.keep_punctuation, &mut rng, &mut generation)?;
+        if args.verbosity {
+          eprintln!("Continuing from {}: {}", args.unit, branch[last]);
+        }
+        check_continued_branch(&mut branch, last, &config)?;
+        (branch, last + 1)
+      },
+      (None, None, None) => {
+        let (starts, ignored) = starts(dict, &config);
+        if args.verbosity && ignored {
+          eprintln!("Every starting {} is excluded by the start words, ignoring them", args.unit);
+        }
+        let branch = generate_branch(dict, &starts, &config, &mut rng, &mut generation)?;
+        if args.verbosity {
+          eprintln!("Starting {}: {}", args.unit, branch[0]);
+        }
+        (branch, 0)
+      }
+    };
+    if args.verbosity {
+      let added = branch.len().saturating_sub(kept);
+      match (args.quote_length, args.max_quote_length) {
+        (_, Some(max_length)) => eprintln!("Quote length: {} (requested {}-{})", added, args.quote_length, max_length),
+        (0, None) => eprintln!("Quote length: {} (unbounded)", added),
+        (length, None) => eprintln!("Quote length: {} (requested {})", added, length)
+      }
+    }
+    // a continued quote is located by the unit it was continued from
+    let first = &branch[kept.saturating_sub(1)];
+    Ok(Quote {
+      text: finish_quote(&branch, &config),
+      length: branch.len(),
+      timecode: dict.timecode(first),
+      trace: generation.trace.unwrap_or_default()
+    })
+  }
+
+  // the traced steps are dropped, see `generate_traced`
+  pub fn generate(dict: &MarkovDict, config: &QuoteConfig) -> Result<String, String> {
+    generate_traced(dict, config).map(|(quote, _)| quote)
+  }
+
+  // also returns the steps of the chain when the config traces it, for the caller to print
+  pub fn generate_traced(dict: &MarkovDict, config: &QuoteConfig) -> Result<(String, Vec<String>), String> {
+    let mut rng = config.rng();
+    let mut generation = Generation::new(dict, config, None);
+    let branch = generate_branch(dict, &starts(dict, config).0, config, &mut rng, &mut generation)?;
+    Ok((finish_quote(&branch, config), generation.trace.unwrap_or_default()))
+  }
+
+  // `choose` receives the current unit and its successors, and returns the index of the next
+  // unit, or None (or an out of bounds index) to pick one at random
+  pub fn generate_with<F>(dict: &MarkovDict, config: &QuoteConfig, mut choose: F) -> Result<String, String>
+    where F: FnMut(&str, &[String]) -> Option<usize> {
+    let mut rng = config.rng();
+    let mut generation = Generation::new(dict, config, Some(&mut choose));
+    let branch = generate_branch(dict, &starts(dict, config).0, config, &mut rng, &mut generation)?;
+    Ok(finish_quote(&branch, config))
+  }
+
+  // picks the next unit in place of the random generator, see `generate_with`
+  type Choose<'a, 'f> = Option<&'a mut (dyn FnMut(&str, &[String]) -> Option<usize> + 'f)>;
+
+  // what a generation carries along its branches: the caller's chooser, the units of the stop
+  // words, the units known to start a forced cycle or not, and the steps of the chain when the
+  // config traces it
+  struct Generation<'a, 'f> {
+    choose: Choose<'a, 'f>,
+    stops: HashSet<u32>,
+    cycles: HashMap<u32, bool>,
+    trace: Option<Vec<String>>
+  }
+
+  impl<'a, 'f> Generation<'a, 'f> {
+    fn new(dict: &MarkovDict, config: &QuoteConfig, choose: Choose<'a, 'f>) -> Self {
+      // the stop words (lowercase already) are looked up once, whatever the case of the units
+      let stops = match config.stop_at.is_empty() {
+        true => HashSet::new(),
+        false => (0..dict.units.len() as u32)
+          .filter(|id| {
+            let unit = dict.unit(*id);
+            config.stop_at.iter().any(|stop| unit.chars().flat_map(char::to_lowercase).eq(stop.chars()))
+          })
+          .collect()
       };
-      let ends_with_reg = Regex::new(r".+[\.!\?]$").unwrap();
-      if !ends_with_reg.is_match(&quote) {
-        quote.push('.');
+      Generation {
+        choose,
+        stops,
+        cycles: HashMap::new(),
+        trace: match config.trace {
+          true => Some(Vec::new()),
+          false => None
+        }
       }
-      return Ok(quote)
-    } else {
-      return Err(String::from("couldn't determine quote starting point: at least 1 capitalized word followed by another world is needed"))
+    }
+    // the step is only formatted when traced
+    fn step<F: FnOnce() -> String>(&mut self, step: F) {
+      if let Some(trace) = &mut self.trace {
+        trace.push(step());
+      }
+    }
+    // the dictionary doesn't change during a generation, so each unit is only followed once
+    fn forced_cycle(&mut self, dict: &MarkovDict, unit: u32) -> bool {
+      *self.cycles.entry(unit).or_insert_with(|| forced_cycle(dict, unit))
     }
   }
 
-  fn build_branch(dict: &HashMap<String, Vec<String>>, unit: String, length: i32) -> Quote {
-    if length == 1 {
-      Node(unit, Box::new(Nil))
-    } else {
-      match dict.get(&unit) {
-        Some(entry) => {
-          let count = entry.iter().count();
-          if count > 0 {
-            let next_index = rand::thread_rng().gen_range(0, entry.iter().count());
-            match entry.iter().nth(next_index) {
-              Some(next) => {
-                return Node(unit, Box::new(build_branch(dict, next.clone(), length - 1)))
-              },
-              None => return Node(unit, Box::new(Nil))
-            };
-          } else {
-            Node(unit, Box::new(Nil))
+  // the starters are computed by the caller, once however many quotes it generates
+  fn generate_branch(
+    dict: &MarkovDict,
+    starts: &[u32],
+    config: &QuoteConfig,
+    rng: &mut ChaCha20Rng,
+    generation: &mut Generation
+  ) -> Result<Vec<String>, String> {
+    let mut remaining = starts.to_vec();
+    let mut branch = pick_branch(dict, &remaining, config, rng, generation)?;
+    let mut longest = branch.clone();
+    // a dead end or a stop word may end the quote short of the minimum length, and a tiny corpus may
+    // go round in circles, another start is tried then; the longest branch is kept if none will do
+    for _ in 0..BRANCH_ATTEMPTS {
+      let short = (branch.len() as i32) < config.min_length;
+      if !short && diverse_enough(&branch, config).is_ok() {
+        break;
+      }
+      if remaining.len() > 1 {
+        if let Some(start) = dict.id(&branch[0]) {
+          remaining.retain(|other| *other != start);
+        }
+      }
+      branch = pick_branch(dict, &remaining, config, rng, generation)?;
+      if branch.len() > longest.len() {
+        longest = branch.clone();
+      }
+    }
+    if (branch.len() as i32) < config.min_length {
+      branch = longest;
+    }
+    check_branch(&mut branch, config)?;
+    Ok(branch)
+  }
+
+  fn check_branch(branch: &mut Vec<String>, config: &QuoteConfig) -> Result<(), String> {
+    fit_branch(branch, config)?;
+    if (branch.len() as i32) < config.min_length {
+      return Err(format!(
+        "couldn't build a quote of at least {} units (got {})", config.min_length, branch.len()
+      ));
+    }
+    diverse_enough(branch, config)
+  }
+
+  // the ratio of distinct units, so that "No no no no" counts a single one
+  fn diverse_enough(branch: &[String], config: &QuoteConfig) -> Result<(), String> {
+    if let Some(min_diversity) = config.min_diversity {
+      let distinct: HashSet<String> = branch.iter().map(|unit| unit.to_lowercase()).collect();
+      let diversity = distinct.len() as f64 / branch.len().max(1) as f64;
+      if diversity < min_diversity {
+        return Err(format!(
+          "couldn't build a quote with a diversity of at least {} (got {:.2})", min_diversity, diversity
+        ));
+      }
+    }
+    Ok(())
+  }
+
+  // the traced steps are dropped, see `generate_continuation_traced`
+  pub fn generate_continuation(
+    dict: &MarkovDict,
+    phrase: &str,
+    config: &QuoteConfig,
+    keep_punctuation: bool
+  ) -> Result<String, String> {
+    generate_continuation_traced(dict, phrase, config, keep_punctuation).map(|(quote, _)| quote)
+  }
+
+  pub fn generate_continuation_traced(
+    dict: &MarkovDict,
+    phrase: &str,
+    config: &QuoteConfig,
+    keep_punctuation: bool
+  ) -> Result<(String, Vec<String>), String> {
+    let mut rng = config.rng();
+    let mut generation = Generation::new(dict, config, None);
+    let (mut branch, last) = continue_branch(dict, phrase, config, keep_punctuation, &mut rng, &mut generation)?;
+    check_continued_branch(&mut branch, last, config)?;
+    Ok((finish_quote(&branch, config), generation.trace.unwrap_or_default()))
+  }
+
+  // the phrase is continued from its last unit having successors, dropping the units after it;
+  // also returns the index of that unit
+  fn continue_branch(
+    dict: &MarkovDict,
+    phrase: &str,
+    config: &QuoteConfig,
+    keep_punctuation: bool,
+    rng: &mut ChaCha20Rng,
+    generation: &mut Generation
+  ) -> Result<(Vec<String>, usize), String> {
+    let stripped = match keep_punctuation {
+      true => String::from(phrase),
+      false => punct_regex().replace_all(phrase, "").into_owned()
+    };
+    let units = split_units(&stripped, &config.unit, keep_punctuation, &config.tokenizer, None);
+    let last = match units.iter().rposition(|unit| dict.contains(unit)) {
+      Some(last) => last,
+      None => return Err(format!(
+        "couldn't continue \"{}\": none of its {}s is followed by anything in the subtitles", phrase, config.unit
+      ))
+    };
+    let mut branch: Vec<String> = units[..last].iter().map(|unit| String::from(*unit)).collect();
+    let length = effective_length(config.pick_length(rng)).saturating_add(1);
+    let start = dict.id(units[last]).unwrap_or_default();
+    branch.extend(dict.units_of(&build_branch(dict, start, length, config, rng, generation)));
+    Ok((branch, last))
+  }
+
+  fn pick_branch(
+    dict: &MarkovDict,
+    starts: &[u32],
+    config: &QuoteConfig,
+    rng: &mut ChaCha20Rng,
+    generation: &mut Generation
+  ) -> Result<Vec<String>, String> {
+    if starts.is_empty() {
+      return Err(String::from("couldn't determine quote starting point: at least 1 capitalized word followed by another world is needed"));
+    }
+    let first = match config.smart_start {
+      true => {
+        // weighted by the number of successors, repeated ones counting each time
+        let weight = |start: &u32| dict.successors[*start as usize].len();
+        let mut pick = rng.gen_range(0, starts.iter().map(weight).sum::<usize>());
+        let mut first = starts[0];
+        for start in starts.iter() {
+          if pick < weight(start) {
+            first = *start;
+            break;
+          }
+          pick -= weight(start);
+        }
+        first
+      },
+      false => starts[rng.gen_range(0, starts.len())]
+    };
+    if config.longest {
+      return Ok(dict.units_of(&longest_branch(dict, first)));
+    }
+    let length = effective_length(config.pick_length(rng));
+    Ok(dict.units_of(&build_branch(dict, first, length, config, rng, generation)))
+  }
+
+  // depth first search for the longest branch using each pair of units once, which ends it; the
+  // search gives up after a number of steps, keeping the longest branch found so far
+  fn longest_branch(dict: &MarkovDict, start: u32) -> Vec<u32> {
+    let distinct = |unit: u32| -> Vec<u32> {
+      let mut next = dict.successors[unit as usize].clone();
+      next.dedup();
+      next
+    };
+    let mut branch = vec![start];
+    let mut longest = branch.clone();
+    let mut used: HashSet<(u32, u32)> = HashSet::new();
+    // the successors of each unit of the branch, and the index of the next one to try
+    let mut pending: Vec<(Vec<u32>, usize)> = vec![(distinct(start), 0)];
+    let mut steps = 0;
+    while let Some((successors, index)) = pending.last_mut() {
+      let current = branch[branch.len() - 1];
+      match successors.get(*index) {
+        Some(next) if steps < LONGEST_SEARCH_STEPS && branch.len() < UNBOUNDED_LENGTH_CAP => {
+          let next = *next;
+          *index += 1;
+          if !used.insert((current, next)) {
+            continue;
           }
+          steps += 1;
+          branch.push(next);
+          if branch.len() > longest.len() {
+            longest = branch.clone();
+          }
+          pending.push((distinct(next), 0));
         },
-        None => Node(unit, Box::new(Nil))
+        _ => {
+          pending.pop();
+          if !pending.is_empty() {
+            branch.pop();
+            used.remove(&(branch[branch.len() - 1], current));
+          }
+        }
+      }
+    }
+    longest
+  }
+
+  // whether following `unit` comes back to it without a choice, each unit on the way having a
+  // single distinct successor, as in a dictionary of one or two keys
+  fn forced_cycle(dict: &MarkovDict, unit: u32) -> bool {
+    let mut current = unit;
+    for _ in 0..dict.successors.len() {
+      let successors = &dict.successors[current as usize];
+      match successors.first() {
+        Some(next) if successors.iter().all(|other| other == next) => current = *next,
+        _ => return false
+      }
+      if current == unit {
+        return true;
+      }
+    }
+    false
+  }
+
+  // starters listed in the config's start words are left out, unless none would remain (then
+  // also returns true)
+  fn starts(dict: &MarkovDict, config: &QuoteConfig) -> (Vec<u32>, bool) {
+    // sorted so that seeded generations don't depend on the dictionary's interning order, and
+    // sentence starts replace the capitalization heuristic when the dictionary has them
+    let mut starts: Vec<u32> = dict.key_ids()
+      .filter(|id| match dict.starters.is_empty() {
+        true => dict.unit(*id).chars().next().is_some_and(char::is_uppercase),
+        false => dict.starters.contains(id)
+      })
+      .collect();
+    starts.sort_by(|a, b| dict.unit(*a).cmp(dict.unit(*b)));
+    let allowed: Vec<u32> = starts.iter()
+      .filter(|start| !config.no_start_words.contains(&dict.unit(**start).to_lowercase()))
+      .cloned()
+      .collect();
+    match allowed.is_empty() {
+      true => {
+        let ignored = !starts.is_empty() && !config.no_start_words.is_empty();
+        (starts, ignored)
+      },
+      false => (allowed, false)
+    }
+  }
+
+  fn fit_branch(branch: &mut Vec<String>, config: &QuoteConfig) -> Result<(), String> {
+    if let Some(max_chars) = config.max_chars {
+      while !branch.is_empty() && finish_quote(branch, config).chars().count() > max_chars {
+        branch.pop();
+      }
+      if branch.is_empty() {
+        return Err(format!("couldn't fit a quote within {} characters", max_chars));
+      }
+    }
+    Ok(())
+  }
+
+  pub fn generate_around(
+    dict: &MarkovDict,
+    reverse: &MarkovDict,
+    word: &str,
+    config: &QuoteConfig
+  ) -> Result<String, String> {
+    let mut rng = config.rng();
+    let (mut branch, index) = around_branch(dict, reverse, word, config, &mut rng)?;
+    check_around_branch(&mut branch, index, config)?;
+    Ok(finish_quote(&branch, config))
+  }
+
+  // the word is looked up as is, then regardless of its case
+  fn find_unit<'a>(dict: &'a MarkovDict, reverse: &'a MarkovDict, word: &'a str) -> Result<&'a str, String> {
+    if dict.contains(word) || reverse.contains(word) {
+      return Ok(word);
+    }
+    let lowercase = word.to_lowercase();
+    let mut matching: Vec<&str> = dict.keys().into_iter().chain(reverse.keys())
+      .filter(|key| key.to_lowercase() == lowercase)
+      .collect();
+    matching.sort();
+    match matching.first() {
+      Some(key) => Ok(*key),
+      None => Err(format!("couldn't find \"{}\" in the dictionary", word))
+    }
+  }
+
+  // also returns the index of the word in the branch
+  fn around_branch(
+    dict: &MarkovDict,
+    reverse: &MarkovDict,
+    word: &str,
+    config: &QuoteConfig,
+    rng: &mut ChaCha20Rng
+  ) -> Result<(Vec<String>, usize), String> {
+    let word = find_unit(dict, reverse, word)?;
+    let mut quote: VecDeque<&str> = VecDeque::new();
+    // a capitalized unit starts a sentence, so nothing is added before it
+    let mut left_open = !word.chars().next().is_some_and(char::is_uppercase);
+    quote.push_back(word);
+    // alternately extend on the right (successors) and on the left (predecessors)
+    let mut right_open = true;
+    let quote_length = effective_length(config.pick_length(rng));
+    let mut index = 0;
+    while quote.len() < quote_length && (right_open || left_open) {
+      if right_open {
+        let last = quote.back().cloned().unwrap_or_default();
+        match dict.next_ids(last) {
+          Some(entry) => quote.push_back(dict.unit(entry[rng.gen_range(0, entry.len())])),
+          None => right_open = false
+        }
+      }
+      if left_open && quote.len() < quote_length {
+        let first = quote.front().cloned().unwrap_or_default();
+        match reverse.next_ids(first) {
+          Some(entry) => {
+            let previous = reverse.unit(entry[rng.gen_range(0, entry.len())]);
+            left_open = !previous.chars().next().is_some_and(char::is_uppercase);
+            quote.push_front(previous);
+            index += 1;
+          },
+          None => left_open = false
+        }
+      }
+    }
+    Ok((quote.into_iter().map(String::from).collect(), index))
+  }
+
+  // trimming the quote to fit must not drop the word it was built around
+  pub fn generate_ending(
+    dict: &MarkovDict,
+    reverse: &MarkovDict,
+    word: &str,
+    config: &QuoteConfig
+  ) -> Result<String, String> {
+    let mut rng = config.rng();
+    let mut branch = ending_branch(dict, reverse, word, config, &mut rng)?;
+    check_ending_branch(&mut branch, config)?;
+    Ok(finish_quote(&branch, config))
+  }
+
+  // the predecessors of the word are walked back until the length, or a capitalized unit starting a
+  // sentence; a branch falling short of the minimum length is tried again, the longest being kept
+  fn ending_branch(
+    dict: &MarkovDict,
+    reverse: &MarkovDict,
+    word: &str,
+    config: &QuoteConfig,
+    rng: &mut ChaCha20Rng
+  ) -> Result<Vec<String>, String> {
+    let word = find_unit(dict, reverse, word)?;
+    let quote_length = effective_length(config.pick_length(rng));
+    let mut longest: VecDeque<&str> = VecDeque::new();
+    for _ in 0..BRANCH_ATTEMPTS {
+      let mut quote: VecDeque<&str> = VecDeque::new();
+      quote.push_back(word);
+      while quote.len() < quote_length {
+        let first = quote.front().cloned().unwrap_or_default();
+        if first.chars().next().is_some_and(char::is_uppercase) {
+          break;
+        }
+        match reverse.next_ids(first) {
+          Some(entry) => quote.push_front(reverse.unit(entry[rng.gen_range(0, entry.len())])),
+          None => break
+        }
+      }
+      if quote.len() > longest.len() {
+        longest = quote;
+      }
+      if longest.len() as i32 >= config.min_length {
+        break;
+      }
+    }
+    if (longest.len() as i32) < config.min_length {
+      return Err(format!(
+        "couldn't build a quote of at least {} units ending on \"{}\" (got {})", config.min_length, word, longest.len()
+      ));
+    }
+    Ok(longest.into_iter().map(String::from).collect())
+  }
+
+  // units are dropped from the start rather than the end to fit the characters, so that the word stays
+  fn check_ending_branch(branch: &mut Vec<String>, config: &QuoteConfig) -> Result<(), String> {
+    if let Some(max_chars) = config.max_chars {
+      while branch.len() > 1 && finish_quote(branch, config).chars().count() > max_chars {
+        branch.remove(0);
+      }
+    }
+    check_branch(branch, config)
+  }
+
+  // the units fit_branch drops must not reach into the continued phrase, up to the unit it's continued from
+  fn check_continued_branch(branch: &mut Vec<String>, last: usize, config: &QuoteConfig) -> Result<(), String> {
+    fit_branch(branch, config)?;
+    if branch.len() <= last {
+      return Err(format!(
+        "the continued phrase exceeds the {} characters of the quote", config.max_chars.unwrap_or_default()
+      ));
+    }
+    check_branch(branch, config)
+  }
+
+  fn check_around_branch(branch: &mut Vec<String>, index: usize, config: &QuoteConfig) -> Result<(), String> {
+    let word = branch[index].clone();
+    check_branch(branch, config)?;
+    if branch.len() <= index {
+      return Err(format!(
+        "couldn't fit a quote including \"{}\" within {} characters", word, config.max_chars.unwrap_or_default()
+      ));
+    }
+    Ok(())
+  }
+
+  fn finish_quote(vec_quote: &[String], config: &QuoteConfig) -> String {
+    let separator = config.joiner();
+    let mut quote = String::new();
+    for (index, unit) in vec_quote.iter().enumerate() {
+      // stray whitespace is dropped, whitespace units (between graphemes) become a single space
+      let unit = match unit.trim() {
+        "" => " ",
+        unit => unit
+      };
+      // closing punctuation sticks to the unit it follows
+      let closing = unit.chars().all(|c| ",.;:!?…)]}%".contains(c));
+      if index > 0 && !closing {
+        quote.push_str(&separator);
+      }
+      quote.push_str(unit);
+    }
+    // runs of spaces are collapsed without a regex, this being called for each unit trimmed to fit
+    let collapsed = quote.trim().split(' ').filter(|part| !part.is_empty()).collect::<Vec<&str>>().join(" ");
+    // a quote never ends on a comma (or other pause), which is replaced by the period unless told otherwise
+    let mut quote = collapsed
+      .trim_end_matches(|c| ",;:".contains(c))
+      .trim_end()
+      .to_string();
+    if !config.no_append && !quote.ends_with(|c| ".!?…".contains(c)) {
+      quote.push('.');
+    }
+    // the first letter, past any opening quote or mark, is titlecased (so "ß" becomes "Ss"), unless a
+    // number comes first
+    match quote.find(char::is_alphanumeric) {
+      Some(first) if !config.no_capitalize && quote[first..].starts_with(char::is_alphabetic) => {
+        let letter = quote[first..].chars().next().unwrap_or_default();
+        format!("{}{}{}", &quote[..first], titlecase(letter), &quote[first + letter.len_utf8()..])
+      },
+      _ => quote
+    }
+  }
+
+  // an unbounded length still needs a cap since chains may cycle forever
+  // library configs aren't validated, so lengths are also kept within bounds here
+  fn effective_length(quote_length: i32) -> usize {
+    match quote_length {
+      0 => UNBOUNDED_LENGTH_CAP,
+      length => (length.max(1) as usize).min(UNBOUNDED_LENGTH_CAP)
+    }
+  }
+
+  fn build_branch(
+    dict: &MarkovDict,
+    unit: u32,
+    length: usize,
+    config: &QuoteConfig,
+    rng: &mut ChaCha20Rng,
+    generation: &mut Generation
+  ) -> Vec<u32> {
+    let mut branch = vec![unit];
+    let mut visited: HashSet<u32> = HashSet::new();
+    visited.insert(unit);
+    while branch.len() < length {
+      let current = branch[branch.len() - 1];
+      let entry = &dict.successors[current as usize];
+      if entry.is_empty() {
+        generation.step(|| format!("{} (no successor)", dict.unit(current)));
+        break;
+      }
+      // the successors are only turned back into units for a caller choosing among them
+      let chosen = generation.choose.as_deref_mut()
+        .and_then(|choose| choose(dict.unit(current), &dict.units_of(entry)));
+      let next = match chosen {
+        Some(index) if index < entry.len() => entry[index],
+        _ => {
+          // successors repeating one of the last two units are avoided when another one exists
+          let previous = branch.len().checked_sub(2).map(|index| branch[index]);
+          let candidates: Vec<u32> = entry.iter()
+            .filter(|next| !config.no_immediate_repeat || (**next != current && Some(**next) != previous))
+            .cloned()
+            .collect();
+          match candidates.len() {
+            0 => entry[rng.gen_range(0, entry.len())],
+            count => candidates[rng.gen_range(0, count)]
+          }
+        }
+      };
+      // once in a loop the chain can't leave, the units said again would only repeat the branch
+      if visited.contains(&next) && generation.forced_cycle(dict, next) {
+        generation.step(|| format!("{} (cycle)", dict.unit(next)));
+        break;
+      }
+      generation.step(|| match entry.len() {
+        1 => format!("{} -> {} (1 candidate)", dict.unit(current), dict.unit(next)),
+        count => format!("{} -> {} ({} candidates)", dict.unit(current), dict.unit(next), count)
+      });
+      branch.push(next);
+      visited.insert(next);
+      if generation.stops.contains(&next) {
+        generation.step(|| format!("{} (stop word)", dict.unit(next)));
+        break;
       }
     }
+    branch
   }
 }
+
+// the types most library users need, for `use subquote::prelude::*`
+pub mod prelude {
+  pub use super::io::{Case, Command, DictFormat, Format, SafeArguments, Tokenizer, Unit};
+  #[cfg(feature = "cli")]
+  pub use super::io::{parse_args, try_parse_args, ParseOutcome};
+  pub use super::builder::{build_dict, generate, BuildOptions, DictCache, MarkovDict, Quote, QuoteConfig};
+}
@@ -1,30 +1,306 @@
 
 use std::env;
+use std::fs::OpenOptions;
+use std::io::{self as stdio, BufRead, Write};
+use std::path::PathBuf;
 use std::process;
+use std::time::Instant;
+use regex::{Captures, Regex};
 use subquote::{
-  io::{self, ParseOutcome},
-  builder
+  io::{self, Command, ParseOutcome, SafeArguments, Unit},
+  builder::{self, CacheStatus, MarkovDict, Quote, QuoteConfig, RecentQuotes}
 };
 
+// documented in the usage
+const EXIT_USAGE: i32 = 2;
+const EXIT_IO: i32 = 3;
+const EXIT_CORPUS: i32 = 4;
+
+// a subtitle read without dialogue gives an empty corpus, any other error is one of I/O
+fn read_exit_code(err: &str) -> i32 {
+  match err.starts_with(builder::NO_DIALOGUE) {
+    true => EXIT_CORPUS,
+    false => EXIT_IO
+  }
+}
+// quotes of the REPL equal to a recent one are generated again at most this many times
+const REPEAT_ATTEMPTS: usize = 10;
+
+// placeholders are checked during validation, unknown ones are left as is
+fn fill_template(template: &str, quote: &Quote, subtitles: &[PathBuf], unit: &Unit) -> String {
+  let placeholder_reg = Regex::new(r"\{([^{}]*)\}").unwrap();
+  placeholder_reg.replace_all(template, |caps: &Captures| {
+    match &caps[1] {
+      "quote" => quote.text.clone(),
+      "source" => subtitles.iter()
+        .map(|subtitle| match subtitle.file_name() {
+          Some(name) => name.to_string_lossy().into_owned(),
+          None => subtitle.display().to_string()
+        })
+        .collect::<Vec<String>>()
+        .join(", "),
+      "length" => quote.length.to_string(),
+      "unit" => unit.to_string(),
+      "timecode" => quote.timecode.map(io::format_time).unwrap_or_default(),
+      _ => caps[0].to_string()
+    }
+  }).into_owned()
+}
+
+// the quote as printed, in its case and template, along with its timecode when asked
+fn render_quote(mut quote: Quote, args: &SafeArguments) -> String {
+  if let Some(case) = args.case {
+    quote.text = case.apply(&quote.text);
+  }
+  let text = match &args.template {
+    Some(template) => fill_template(template, &quote, &args.subtitles, &args.unit),
+    None => quote.to_string()
+  };
+  // quotes from caches written before timecodes were recorded have none
+  match (args.with_timecode, quote.timecode) {
+    (true, Some(timecode)) => format!("[{}] {}", io::format_time(timecode), text),
+    _ => text
+  }
+}
+
+// the steps of a traced chain go to the standard error, apart from the quotes
+fn print_trace(trace: &[String]) {
+  for step in trace.iter() {
+    eprintln!("{}", step);
+  }
+}
+
+// quotes go to the output file when there's one, the standard output otherwise
+fn open_output(args: &SafeArguments) -> Box<dyn Write> {
+  let output_file = match &args.output_file {
+    Some(output_file) => output_file,
+    None => return Box::new(stdio::stdout())
+  };
+  let opened = OpenOptions::new()
+    .write(true)
+    .create(true)
+    .append(args.append_output)
+    .truncate(!args.append_output)
+    .open(output_file);
+  match opened {
+    Ok(file) => Box::new(file),
+    Err(err) => {
+      println!("Error while opening output file: {}.", err);
+      process::exit(EXIT_IO);
+    }
+  }
+}
+
+// flushed right away, so that a file being followed gets each quote of the REPL as it comes
+fn write_quote(output: &mut dyn Write, quote: &str) {
+  if let Err(err) = writeln!(output, "{}", quote).and_then(|_| output.flush()) {
+    println!("Error while writing quote: {}.", err);
+    process::exit(EXIT_IO);
+  }
+}
+
+// quotes are printed as they come, errors don't end the session
+fn run_repl(corpus: &MarkovDict, args: &SafeArguments, output: &mut dyn Write) {
+  let mut config = QuoteConfig::from(args);
+  let mut generated: u64 = 0;
+  let mut recent = RecentQuotes::new(args.no_repeat_window.unwrap_or_default());
+  let stdin = stdio::stdin();
+  loop {
+    eprint!("> ");
+    stdio::stderr().flush().ok();
+    let mut line = String::new();
+    match stdin.lock().read_line(&mut line) {
+      Ok(0) | Err(_) => break,
+      Ok(_) => ()
+    }
+    let mut command = line.trim().splitn(2, char::is_whitespace);
+    let start = match (command.next().unwrap_or_default(), command.next().map(str::trim)) {
+      ("", _) => None,
+      (":start", Some(word)) if !word.is_empty() => Some(word),
+      (":len", Some(length)) => {
+        match length.parse::<i32>() {
+          // lengths over the ceiling are clamped by the generation
+          Ok(length) if length >= 0 => config = config.length(length),
+          _ => eprintln!("Invalid length: {}", length)
+        }
+        continue;
+      },
+      (":quit", None) => break,
+      _ => {
+        eprintln!("Unknown command (expected an empty line, \":start WORD\", \":len N\" or \":quit\")");
+        continue;
+      }
+    };
+    let mut quote = Err(String::new());
+    for _ in 0..REPEAT_ATTEMPTS {
+      // seeded sessions stay reproducible without repeating the same quote
+      if let Some(seed) = args.seed {
+        config = config.seed(seed.wrapping_add(generated));
+      }
+      quote = match start {
+        None => builder::generate_traced(corpus, &config),
+        Some(word) => builder::generate_continuation_traced(corpus, word, &config, args.keep_punctuation)
+      }.map(|(quote, trace)| {
+        print_trace(&trace);
+        quote
+      });
+      generated += 1;
+      match &quote {
+        Ok(text) if recent.contains(text) => (),
+        _ => break
+      }
+    }
+    if let Ok(text) = &quote {
+      if recent.contains(text) {
+        quote = Err(format!(
+          "couldn't generate a quote other than the last {}", args.no_repeat_window.unwrap_or_default()
+        ));
+      } else {
+        recent.push(text);
+      }
+    }
+    match quote {
+      Ok(quote) => match args.case {
+        Some(case) => write_quote(output, &case.apply(&quote)),
+        None => write_quote(output, &quote)
+      },
+      Err(err) => eprintln!("Error while generating quote: {}.", err)
+    }
+  }
+}
+
 fn main() {
   let args: Vec<String> = env::args().collect();
+  let parsing = Instant::now();
   let unsafe_arguments = io::parse_args(&args).unwrap_or_else(|outcome| {
     match outcome {
-      ParseOutcome::Error(err) => {
-        println!("Error during arguments parsing: {}.", err);
-        process::exit(1);
+      ParseOutcome::Error(_) => {
+        println!("Error while parsing arguments: {}.", outcome);
+        process::exit(EXIT_USAGE);
       },
-      ParseOutcome::Help => process::exit(1)
+      ParseOutcome::Help(_) => process::exit(1),
+      ParseOutcome::Version => {
+        println!("{}", outcome);
+        process::exit(0);
+      }
     }
   });
-  let safe_arguments = unsafe_arguments.validate().unwrap_or_else(|err| {
-    println!("Error during input validation: [{}].", err);
-    process::exit(1);
+  let mut safe_arguments = unsafe_arguments.validate().unwrap_or_else(|errors| {
+    println!("Invalid arguments:");
+    for err in errors.iter() {
+      println!("  - {}", err);
+    }
+    process::exit(EXIT_USAGE);
   });
+  if safe_arguments.verbosity {
+    eprintln!("Parsed the arguments in {:.2?}", parsing.elapsed());
+  }
+
+  match safe_arguments.command {
+    Command::Build => {
+      let caches = builder::build_caches(safe_arguments).unwrap_or_else(|err| {
+        println!("Error while building caches: {}.", err);
+        process::exit(read_exit_code(&err));
+      });
+      for cache in caches.iter() {
+        println!("{}", cache.display());
+      }
+      return;
+    },
+    Command::Stats => {
+      let stats = builder::get_stats(&safe_arguments).unwrap_or_else(|err| {
+        println!("Error while inspecting subtitles: {}.", err);
+        process::exit(EXIT_IO);
+      });
+      for (subtitle, stats) in stats.iter() {
+        println!("{}: {}", subtitle.display(), stats);
+      }
+      return;
+    },
+    Command::Verify => {
+      let caches = builder::verify_caches(&safe_arguments).unwrap_or_else(|err| {
+        println!("Error while verifying caches: {}.", err);
+        process::exit(EXIT_IO);
+      });
+      let outdated = caches.iter().filter(|(_, status)| *status == CacheStatus::Outdated).count();
+      let broken = caches.iter().filter(|(_, status)| matches!(status, CacheStatus::Broken(_))).count();
+      // outdated caches only warn, they still give quotes
+      for (cache, status) in caches.iter() {
+        match status {
+          CacheStatus::Healthy => (),
+          CacheStatus::Outdated => println!("{}: warning: {}", cache.display(), status),
+          CacheStatus::Broken(_) => println!("{}: {}", cache.display(), status)
+        }
+      }
+      println!(
+        "{} healthy caches, {} outdated, {} that can't be used", caches.len() - outdated - broken, outdated, broken
+      );
+      if broken > 0 {
+        process::exit(EXIT_IO);
+      }
+      return;
+    },
+    Command::Generate => ()
+  }
 
-  let quote = builder::get_quote(safe_arguments).unwrap_or_else(|err| {
-    println!("Error during quote generation: [{}].", err);
-    process::exit(1);
+  if safe_arguments.dump_dialogue {
+    let dialogue = builder::get_dialogue(safe_arguments).unwrap_or_else(|err| {
+      println!("Error while extracting dialogue: {}.", err);
+      process::exit(EXIT_IO);
+    });
+    println!("{}", dialogue);
+    return;
+  }
+
+  let corpus = builder::get_corpus(&safe_arguments).unwrap_or_else(|err| {
+    println!("Error while reading subtitles: {}.", err);
+    process::exit(read_exit_code(&err));
   });
-  println!("{}", quote);
+  if let Some(word) = &safe_arguments.successors {
+    let counts = corpus.successor_counts(word.trim()).unwrap_or_else(|| {
+      println!("Error while inspecting successors: \"{}\" isn't followed by anything in the subtitles.", word);
+      process::exit(EXIT_CORPUS);
+    });
+    for (next, count) in counts.iter() {
+      println!("{} {}", count, next);
+    }
+    return;
+  }
+  if safe_arguments.repl {
+    run_repl(&corpus, &safe_arguments, &mut open_output(&safe_arguments));
+    return;
+  }
+  // candidate K is the quote of seed SEED + K - 1, so that a later run can pick it again
+  let mut candidates = Vec::new();
+  if let Some(preview) = safe_arguments.preview {
+    let seed = safe_arguments.seed.unwrap_or_else(rand::random);
+    match safe_arguments.pick {
+      Some(pick) => safe_arguments.seed = Some(seed.wrapping_add(pick as u64 - 1)),
+      None => {
+        for candidate in 1..=preview {
+          safe_arguments.seed = Some(seed.wrapping_add(candidate as u64 - 1));
+          let quote = builder::quote_from(&corpus, &safe_arguments).unwrap_or_else(|err| {
+            println!("Error while generating quote: {}.", err);
+            process::exit(EXIT_CORPUS);
+          });
+          print_trace(&quote.trace);
+          candidates.push(format!("{}. {}", candidate, render_quote(quote, &safe_arguments)));
+        }
+        eprintln!("Print candidate K alone with \"--seed {} --preview {} --pick K\"", seed, preview);
+      }
+    }
+  }
+  if candidates.is_empty() {
+    let quote = builder::quote_from(&corpus, &safe_arguments).unwrap_or_else(|err| {
+      println!("Error while generating quote: {}.", err);
+      process::exit(EXIT_CORPUS);
+    });
+    print_trace(&quote.trace);
+    candidates.push(render_quote(quote, &safe_arguments));
+  }
+  // opened once the quotes are there, so that a failed generation leaves the file as it was
+  let mut output = open_output(&safe_arguments);
+  for candidate in candidates.iter() {
+    write_quote(&mut output, candidate);
+  }
 }
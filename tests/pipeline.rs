@@ -0,0 +1,1557 @@
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use subquote::{
+  io::{self, ParseOutcome, SafeArguments, Tokenizer, Unit},
+  builder::{self, BuildOptions, CacheStatus, MarkovDict, QuoteConfig}
+};
+use tempfile::TempDir;
+
+const SUBTITLE: &str = "1
+00:00:01,000 --> 00:00:02,000
+Hello there my friend
+
+2
+00:00:03,000 --> 00:00:04,000
+<i>[door closes]</i>
+";
+
+fn write_subtitle(dir: &Path, name: &str, content: &str) -> PathBuf {
+  let path = dir.join(name);
+  fs::write(&path, content).unwrap();
+  path
+}
+
+fn cues(lines: &[&str]) -> String {
+  lines.iter().enumerate()
+    .map(|(index, line)| format!("{}\n00:00:0{},000 --> 00:00:0{},500\n{}\n", index + 1, index, index, line))
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+fn argv(args: &[&str]) -> Vec<String> {
+  let mut argv = vec![String::from("subquote")];
+  argv.extend(args.iter().map(|arg| arg.to_string()));
+  argv
+}
+
+fn valid(args: &[&str]) -> SafeArguments {
+  io::parse_args(&argv(args)).ok().unwrap().validate().unwrap()
+}
+
+// the arguments generating from `subtitle`, which parse whether they're valid or not
+fn validated(subtitle: &Path, extra: &[&str]) -> Result<SafeArguments, Vec<String>> {
+  let mut args = vec![subtitle.to_str().unwrap()];
+  args.extend_from_slice(extra);
+  io::parse_args(&argv(&args)).ok().unwrap().validate()
+}
+
+fn parsed(subtitle: &Path, extra: &[&str]) -> SafeArguments {
+  validated(subtitle, extra).unwrap()
+}
+
+fn cache_dir(dir: &Path) -> PathBuf {
+  let cache = dir.join("cache");
+  fs::create_dir(&cache).unwrap();
+  cache
+}
+
+#[test]
+fn generates_a_quote_and_caches_the_dictionary() {
+  let dir = TempDir::new().unwrap();
+  let subtitle = write_subtitle(dir.path(), "show.srt", SUBTITLE);
+  let cache = cache_dir(dir.path());
+
+  let quote = builder::get_quote(parsed(&subtitle, &["--cache", cache.to_str().unwrap(), "--seed", "7"])).unwrap();
+
+  assert_eq!(quote.text, "Hello there my friend.");
+  assert_eq!(quote.length, 4);
+  assert!(cache.join("show.srt.word").is_file());
+}
+
+#[test]
+fn generates_a_quote_from_arguments_built_without_parsing() {
+  let dir = TempDir::new().unwrap();
+  let subtitle = write_subtitle(dir.path(), "show.srt", SUBTITLE);
+  let args = SafeArguments { seed: Some(7), ..SafeArguments::with_subtitles(vec![subtitle]) };
+
+  assert_eq!(builder::get_quote(args).unwrap().text, "Hello there my friend.");
+  assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+}
+
+#[test]
+fn shares_the_defaults_of_the_command_line() {
+  let dir = TempDir::new().unwrap();
+  let subtitle = write_subtitle(dir.path(), "show.srt", SUBTITLE);
+  let built = SafeArguments::with_subtitles(vec![subtitle.clone()]);
+
+  for args in [parsed(&subtitle, &["--no-cache"]), built].iter() {
+    assert_eq!((args.quote_length, args.verbosity), (io::DEFAULT_WORD_LENGTH, io::DEFAULT_VERBOSITY));
+    assert!(args.unit == io::DEFAULT_UNIT);
+  }
+  assert_eq!(parsed(&subtitle, &["--no-cache", "--unit", "char"]).quote_length, io::DEFAULT_GRAPHEME_LENGTH);
+}
+
+// the environment is only set for the runs of the binary, the tests sharing the process
+#[test]
+fn prefers_the_cache_flag_over_the_environment() {
+  let dir = TempDir::new().unwrap();
+  let subtitle = write_subtitle(dir.path(), "show.srt", SUBTITLE);
+  let (env_cache, xdg_cache, flag_cache) = (dir.path().join("env"), dir.path().join("xdg"), dir.path().join("flag"));
+  fs::create_dir(&flag_cache).unwrap();
+  let run = |extra: &[&str]| {
+    Command::new(env!("CARGO_BIN_EXE_subquote"))
+      .arg(&subtitle)
+      .args(extra)
+      .env("SUBQUOTE_CACHE_DIR", &env_cache)
+      .env("XDG_CACHE_HOME", &xdg_cache)
+      .stdout(Stdio::null())
+      .status()
+      .unwrap()
+  };
+
+  assert!(run(&[]).success());
+  assert!(env_cache.join("show.srt.word").is_file());
+  assert!(!xdg_cache.exists());
+
+  assert!(run(&["--cache", flag_cache.to_str().unwrap(), "-u", "char"]).success());
+  assert!(flag_cache.join("show.srt.char").is_file());
+  assert!(!env_cache.join("show.srt.char").exists());
+}
+
+#[test]
+fn only_uses_cues_within_the_time_range() {
+  let dir = TempDir::new().unwrap();
+  let subtitle = write_subtitle(dir.path(), "show.srt", &cues(&["Good morning sir", "Good evening sir", "Good night sir"]));
+  let cache = cache_dir(dir.path());
+  let args = parsed(&subtitle, &["--cache", cache.to_str().unwrap(), "--from-time", "00:00:01", "--to-time", "00:00:01"]);
+  let quote = builder::get_quote(args).unwrap();
+
+  assert_eq!(quote.text, "Good evening sir.");
+  assert!(cache.join("show.srt.1s-1s.word").is_file());
+}
+
+#[test]
+fn skips_the_listed_cues() {
+  let dir = TempDir::new().unwrap();
+  let lines = ["Previously on the show", "Good evening sir", "Subtitles by someone", "Synced by someone"];
+  let subtitle = write_subtitle(dir.path(), "show.srt", &cues(&lines));
+  let cache = cache_dir(dir.path());
+  let quote = builder::get_quote(parsed(&subtitle, &["--cache", cache.to_str().unwrap(), "--skip-cues", "1, 3-4"])).unwrap();
+
+  assert_eq!(quote.text, "Good evening sir.");
+  assert!(cache.join("show.srt.skip-1_3-4.word").is_file());
+  for ranges in ["4-3", "1,,2", "x"].iter() {
+    assert!(io::parse_args(&argv(&["show.srt", "--skip-cues", ranges])).is_err());
+  }
+}
+
+#[test]
+fn counts_lines_repeated_by_the_next_cue_once() {
+  let dir = TempDir::new().unwrap();
+  let lines = ["Good morning sir", "Good morning sir", "Good evening", "Good morning sir"];
+  let subtitle = write_subtitle(dir.path(), "show.srt", &cues(&lines));
+  let corpus_of = |extra: &[&str]| builder::get_corpus(&parsed(&subtitle, extra)).unwrap();
+
+  assert_eq!(corpus_of(&["--no-cache"]).successors("Good"), Some(vec!["evening", "morning", "morning"]));
+  assert_eq!(corpus_of(&["--no-cache"]).timecode("morning"), Some(Duration::from_secs(0)));
+  assert_eq!(
+    corpus_of(&["--no-cache", "--keep-duplicates"]).successors("Good"),
+    Some(vec!["evening", "morning", "morning", "morning"])
+  );
+  // a cue saying a line twice on purpose, each said by another speaker
+  let subtitle = write_subtitle(dir.path(), "twice.srt", &cues(&["- Good night\n- Good night", "- Good night"]));
+  let dict = builder::get_corpus(&parsed(&subtitle, &["--no-cache"])).unwrap();
+  assert_eq!(dict.successors("Good"), Some(vec!["night", "night"]));
+}
+
+#[test]
+fn keeps_the_timecode_of_the_first_unit_through_the_cache() {
+  let dir = TempDir::new().unwrap();
+  let subtitle = write_subtitle(dir.path(), "show.srt", &cues(&["Well well", "Good morning sir", "Good night sir"]));
+  let cache = cache_dir(dir.path());
+  let args = ["--cache", cache.to_str().unwrap(), "--must-include", "morning", "--with-timecode"];
+
+  for _ in 0..2 {
+    let safe_arguments = parsed(&subtitle, &args);
+    assert!(safe_arguments.with_timecode);
+    let quote = builder::get_quote(safe_arguments).unwrap();
+    assert_eq!(quote.text, "Good morning sir.");
+    assert_eq!(quote.timecode, Some(Duration::from_secs(1)));
+  }
+}
+
+#[test]
+fn rebuilds_a_corrupt_cache() {
+  let dir = TempDir::new().unwrap();
+  let subtitle = write_subtitle(dir.path(), "show.srt", SUBTITLE);
+  let cache = cache_dir(dir.path());
+  fs::write(cache.join("show.srt.word"), "{\"entries\": [").unwrap();
+
+  let safe_arguments = parsed(&subtitle, &["--cache", cache.to_str().unwrap()]);
+
+  assert_eq!(builder::get_quote(safe_arguments).unwrap().text, "Hello there my friend.");
+  assert!(fs::read_to_string(cache.join("show.srt.word")).unwrap().starts_with("{\"entries\":[{"));
+}
+
+#[test]
+fn inspects_a_subtitle() {
+  let dir = TempDir::new().unwrap();
+  let subtitle = write_subtitle(dir.path(), "show.srt", SUBTITLE);
+  let stats = builder::inspect_subtitle(&subtitle, &io::Unit::Word).unwrap();
+
+  assert_eq!(stats, builder::CorpusStats { cues: 2, lines: 1, keys: 3, starters: 1, transitions: 3, variety: None });
+  assert!(builder::inspect_subtitle(&dir.path().join("missing.srt"), &io::Unit::Word).is_err());
+  let ass = write_subtitle(
+    dir.path(),
+    "show.ass",
+    "[Script Info]\n[Events]\nDialogue: 0,0:00:01.00,0:00:02.00,Default,,0,0,0,,Hello there my friend\n"
+  );
+  let text = write_subtitle(dir.path(), "show.txt", "Hello there my friend\n");
+  for subtitle in [ass, text].iter() {
+    let stats = builder::inspect_subtitle(subtitle, &io::Unit::Word).unwrap();
+    assert_eq!((stats.cues, stats.lines, stats.keys), (1, 1, 3), "{}", subtitle.display());
+  }
+}
+
+#[test]
+fn estimates_the_variety_of_quotes() {
+  let dir = TempDir::new().unwrap();
+  let subtitle = write_subtitle(dir.path(), "show.srt", &cues(&["Good morning sir", "Good evening sir"]));
+  let stats = builder::get_stats(&valid(&["stats", subtitle.to_str().unwrap(), "--no-cache", "--estimate-variety", "-l", "3"])).unwrap();
+  let variety = stats[0].1.variety.as_ref().unwrap();
+
+  assert_eq!((variety.starts, variety.length), (1, 3));
+  assert!((variety.branching - 2_f64.cbrt()).abs() < 1e-9);
+  assert!((variety.log10_quotes - 2_f64.log10() * 2.0 / 3.0).abs() < 1e-9);
+  assert!(stats[0].1.to_string().ends_with("1 starts, 1.26 successors per key (geometric mean), about 10^0.2 quotes of 3 units"));
+  let args = argv(&[subtitle.to_str().unwrap(), "--estimate-variety"]);
+  assert!(matches!(io::parse_args(&args), Err(ParseOutcome::Error(_))));
+}
+
+#[test]
+fn builds_caches_without_generating() {
+  let dir = TempDir::new().unwrap();
+  let subtitle = write_subtitle(dir.path(), "show.srt", SUBTITLE);
+  let cache = cache_dir(dir.path());
+  let safe_arguments = valid(&["build", subtitle.to_str().unwrap(), "--cache", cache.to_str().unwrap(), "-u", "word,char"]);
+
+  assert_eq!(safe_arguments.command, io::Command::Build);
+  assert_eq!(builder::build_caches(safe_arguments).unwrap(), vec![cache.join("show.srt.word")]);
+  assert!(cache.join("show.srt.char").is_file());
+}
+
+#[test]
+fn parses_each_subcommand_with_its_own_options() {
+  let outcome = |args: &[&str]| match io::parse_args(&argv(args)) {
+    Err(ParseOutcome::Error(err)) => err,
+    _ => String::new()
+  };
+
+  assert_eq!(outcome(&["build", "show.srt", "--seed", "1"]), "option \"seed\" can't be used with subcommand \"build\"");
+  assert_eq!(outcome(&["stats", "show.srt", "--json-dict-out", "out.json"]), "option \"json-dict-out\" can't be used with subcommand \"stats\"");
+  assert_eq!(outcome(&["verify", "-u", "char"]), "option \"unit\" can't be used with subcommand \"verify\"");
+  assert_eq!(outcome(&["show.srt", "--estimate-variety"]), "option \"estimate-variety\" can't be used with subcommand \"gen\"");
+  assert!(io::parse_args(&argv(&["stats", "show.srt", "--no-start-words", "words.txt", "-l", "3"])).is_ok());
+}
+
+#[test]
+fn reads_a_subtitle_named_like_a_subcommand() {
+  let dir = TempDir::new().unwrap();
+  write_subtitle(dir.path(), "build", SUBTITLE);
+  let run = |args: &[&str]| {
+    Command::new(env!("CARGO_BIN_EXE_subquote")).args(args).current_dir(dir.path()).output().unwrap()
+  };
+
+  for args in [&["gen", "build", "--no-cache"][..], &["--no-cache", "--", "build"]].iter() {
+    let output = run(args);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "Hello there my friend.\n");
+  }
+  let output = run(&["build", "--no-cache"]);
+  assert_eq!(output.status.code(), Some(2));
+  assert!(String::from_utf8(output.stdout).unwrap().contains("use \"gen build\" or \"-- build\" for the file"));
+}
+
+#[test]
+fn seeded_quotes_are_reproducible() {
+  let dir = TempDir::new().unwrap();
+  let subtitle = write_subtitle(dir.path(), "show.srt", "1
+00:00:01,000 --> 00:00:02,000
+The cat sat on the mat and the dog sat on the cat
+
+2
+00:00:03,000 --> 00:00:04,000
+The dog ran on the road and the cat ran on the mat
+");
+  let quote = || builder::get_quote(parsed(&subtitle, &["--no-cache", "--seed", "42", "-l", "8"])).unwrap();
+
+  assert_eq!(quote(), quote());
+}
+
+#[test]
+fn creates_the_default_cache_directory_from_concurrent_runs() {
+  let dir = TempDir::new().unwrap();
+  let subtitle = write_subtitle(dir.path(), "show.srt", SUBTITLE);
+  let cache_home = dir.path().join("cache-home");
+  let runs: Vec<_> = (0..8)
+    .map(|_| {
+      Command::new(env!("CARGO_BIN_EXE_subquote"))
+        .arg(&subtitle)
+        .env_remove("SUBQUOTE_CACHE_DIR")
+        .env("XDG_CACHE_HOME", &cache_home)
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap()
+    })
+    .collect();
+
+  for mut run in runs {
+    assert!(run.wait().unwrap().success());
+  }
+  assert!(cache_home.join("subquote").is_dir());
+  let output = Command::new(env!("CARGO_BIN_EXE_subquote"))
+    .arg(&subtitle)
+    .env_remove("SUBQUOTE_CACHE_DIR")
+    .env("XDG_CACHE_HOME", &subtitle)
+    .output()
+    .unwrap();
+  assert_eq!(output.status.code(), Some(2));
+  assert!(String::from_utf8(output.stdout).unwrap().contains("couldn't create cache directory"));
+}
+
+#[test]
+fn falls_back_to_the_home_directory_as_is() {
+  let dir = TempDir::new().unwrap();
+  let subtitle = write_subtitle(dir.path(), "show.srt", SUBTITLE);
+  let status = Command::new(env!("CARGO_BIN_EXE_subquote"))
+    .arg(&subtitle)
+    .current_dir(dir.path())
+    .env_remove("SUBQUOTE_CACHE_DIR")
+    .env_remove("XDG_CACHE_HOME")
+    .env("HOME", "home")
+    .stdout(Stdio::null())
+    .status()
+    .unwrap();
+
+  assert!(status.success());
+  assert!(dir.path().join("home/.cache/subquote/show.srt.word").is_file());
+}
+
+#[test]
+fn reads_the_subtitles_of_a_directory() {
+  let dir = TempDir::new().unwrap();
+  let season = dir.path().join("season");
+  fs::create_dir_all(season.join("extras")).unwrap();
+  write_subtitle(&season, "e02.srt", &cues(&["Good evening"]));
+  write_subtitle(&season, "e01.SRT", &cues(&["Good morning"]));
+  write_subtitle(&season, "notes.txt", &cues(&["Good grief"]));
+  write_subtitle(&season.join("extras"), "e01.vtt", &cues(&["Good night"]));
+  let subtitles_of = |extra: &[&str]| validated(&season, extra).map(|args| args.subtitles);
+
+  assert_eq!(subtitles_of(&[]).unwrap(), vec![season.join("e01.SRT"), season.join("e02.srt")]);
+  assert_eq!(
+    subtitles_of(&["-r"]).unwrap(),
+    vec![season.join("e01.SRT"), season.join("e02.srt"), season.join("extras").join("e01.vtt")]
+  );
+  assert_eq!(subtitles_of(&["--recursive", "--glob", "e01.*"]).unwrap().len(), 2);
+  assert_eq!(subtitles_of(&["--glob", "*.txt"]).unwrap(), vec![season.join("notes.txt")]);
+  let errors = subtitles_of(&["--glob", "*.ass"]).err().unwrap();
+  assert!(errors[0].starts_with("found no subtitle matching \"*.ass\""), "{:?}", errors);
+}
+
+#[cfg(unix)]
+#[test]
+fn reads_each_directory_once_through_symbolic_links() {
+  let dir = TempDir::new().unwrap();
+  let season = dir.path().join("season");
+  fs::create_dir_all(season.join("extras")).unwrap();
+  write_subtitle(&season, "e01.srt", &cues(&["Good morning"]));
+  write_subtitle(&season.join("extras"), "e02.srt", &cues(&["Good night"]));
+  std::os::unix::fs::symlink(&season, season.join("extras").join("back")).unwrap();
+  std::os::unix::fs::symlink(season.join("extras"), season.join("more")).unwrap();
+
+  assert_eq!(
+    validated(&season, &["-r"]).unwrap().subtitles,
+    vec![season.join("e01.srt"), season.join("extras").join("e02.srt")]
+  );
+}
+
+#[test]
+fn reports_a_missing_subtitle() {
+  let dir = TempDir::new().unwrap();
+  let missing = dir.path().join("missing.srt");
+  let args = argv(&[missing.to_str().unwrap(), "--no-cache"]);
+  let errors = io::parse_args(&args).ok().unwrap().validate().err().unwrap();
+
+  assert!(errors.iter().any(|err| err.contains("specified subtitle is not a file")));
+}
+
+#[test]
+fn reports_a_subtitle_that_isnt_utf8() {
+  let dir = TempDir::new().unwrap();
+  let subtitle = dir.path().join("show.srt");
+  fs::write(&subtitle, b"1\n00:00:01,000 --> 00:00:02,000\nCaf\xe9 au lait\n").unwrap();
+  let err = builder::get_quote(parsed(&subtitle, &["--no-cache"])).err().unwrap();
+
+  assert!(err.contains("isn't valid UTF-8"));
+
+  let quote = builder::get_quote(parsed(&subtitle, &["--no-cache", "--encoding", "latin1"])).unwrap();
+
+  assert_eq!(quote.text, "Café au lait.");
+}
+
+#[test]
+fn caches_each_extraction_command_apart() {
+  let dir = TempDir::new().unwrap();
+  let subtitle = write_subtitle(dir.path(), "show.srt", SUBTITLE);
+  let cache = cache_dir(dir.path());
+  let extracted = |cmd: &str| {
+    builder::get_quote(parsed(&subtitle, &["--cache", cache.to_str().unwrap(), "--extract-cmd", cmd])).unwrap().text
+  };
+
+  assert_eq!(extracted("cat {}"), "Hello there my friend.");
+  assert_eq!(extracted("sed s/friend/foe/ {}"), "Hello there my foe.");
+  assert_eq!(fs::read_dir(&cache).unwrap().count(), 2);
+}
+
+#[test]
+fn caches_each_encoding_apart() {
+  let dir = TempDir::new().unwrap();
+  let subtitle = dir.path().join("show.srt");
+  fs::write(&subtitle, b"1\n00:00:01,000 --> 00:00:02,000\nCaf\xe9 au lait\n").unwrap();
+  let cache = cache_dir(dir.path());
+  let decoded = |encoding: &str| {
+    builder::get_quote(parsed(&subtitle, &["--cache", cache.to_str().unwrap(), "--encoding", encoding])).unwrap().text
+  };
+
+  assert_eq!(decoded("latin1"), "Café au lait.");
+  assert_eq!(decoded("koi8-r"), "CafИ au lait.");
+  assert!(cache.join("show.srt.word-encoding-windows-1252").is_file());
+  assert_eq!(fs::read_dir(&cache).unwrap().count(), 2);
+}
+
+#[test]
+fn caches_each_forced_format_apart() {
+  let dir = TempDir::new().unwrap();
+  let subtitle = write_subtitle(dir.path(), "show.srt", &cues(&["Hello there"]));
+  let cache = cache_dir(dir.path());
+  let corpus_of = |extra: &[&str]| {
+    builder::get_corpus(&parsed(&subtitle, &[&["--cache", cache.to_str().unwrap()], extra].concat()))
+  };
+
+  assert_eq!(corpus_of(&[]).unwrap().successors("Hello"), Some(vec!["there"]));
+  // the cache of the SubRip cues isn't read as SubStation Alpha, which has no dialogue here
+  assert!(corpus_of(&["--format", "ass"]).is_err());
+  assert_eq!(corpus_of(&["--format", "text"]).unwrap().successors("Hello"), Some(vec!["there"]));
+  assert!(cache.join("show.srt.word-format-text").is_file());
+  assert_eq!(fs::read_dir(&cache).unwrap().count(), 2);
+}
+
+#[test]
+fn reports_an_unknown_encoding() {
+  let args = argv(&["show.srt", "--encoding", "klingon"]);
+
+  assert!(matches!(io::parse_args(&args), Err(ParseOutcome::Error(_))));
+}
+
+#[test]
+fn reports_a_subtitle_without_dialogue() {
+  let dir = TempDir::new().unwrap();
+  let subtitle = write_subtitle(dir.path(), "show.srt", &cues(&["[music]", "♪ ♪", "Hey"]));
+  let err = builder::get_quote(parsed(&subtitle, &["--no-cache"])).err().unwrap();
+
+  assert_eq!(err, "subtitle has too little dialogue to generate a quote");
+}
+
+#[test]
+fn reports_an_empty_subtitle() {
+  let dir = TempDir::new().unwrap();
+  let cache = cache_dir(dir.path());
+
+  for content in ["", " \n\r\n\t", &cues(&["[music]"])].iter() {
+    let subtitle = write_subtitle(dir.path(), "show.srt", content);
+    let err = builder::get_quote(parsed(&subtitle, &["--cache", cache.to_str().unwrap()])).err().unwrap();
+    assert!(err.starts_with(builder::NO_DIALOGUE), "{}", err);
+  }
+  assert!(!cache.join("show.srt.word").exists());
+  // an empty corpus, not a failed read
+  for subcommand in ["gen", "build"].iter() {
+    let status = Command::new(env!("CARGO_BIN_EXE_subquote"))
+      .args([subcommand, dir.path().join("show.srt").to_str().unwrap(), "--cache", cache.to_str().unwrap()])
+      .output()
+      .unwrap()
+      .status;
+    assert_eq!(status.code(), Some(4), "{}", subcommand);
+  }
+}
+
+#[test]
+fn reports_an_unknown_unit() {
+  let args = argv(&["show.srt", "--unit", "sentence"]);
+
+  assert!(matches!(io::parse_args(&args), Err(ParseOutcome::Error(_))));
+}
+
+#[test]
+fn describes_parse_outcomes() {
+  let outcome = io::parse_args(&argv(&["--version"])).err().unwrap();
+  let error: Box<dyn std::error::Error> = Box::new(io::parse_args(&argv(&["--unit", "sentence"])).err().unwrap());
+
+  assert_eq!(outcome, ParseOutcome::Version);
+  match io::try_parse_args(&argv(&["--help"])) {
+    Err(ParseOutcome::Help(usage)) => assert!(usage.starts_with("Usage: subquote [gen] FILE.srt...")),
+    _ => panic!("expected the usage")
+  }
+  assert_eq!(outcome.to_string(), format!("subquote {}", env!("CARGO_PKG_VERSION")));
+  assert!(error.to_string().contains("sentence"));
+}
+
+#[test]
+fn lists_examples_that_parse() {
+  let usage = match io::try_parse_args(&argv(&["--help-format", "examples"])) {
+    Err(ParseOutcome::Help(usage)) => usage,
+    _ => panic!("expected the usage")
+  };
+  let (options, examples) = usage.split_at(usage.find("Examples:").unwrap());
+  let invocations: Vec<&str> = examples.lines()
+    .filter_map(|line| line.split("subquote ").nth(1))
+    .map(|invocation| invocation.split(';').next().unwrap())
+    .collect();
+
+  assert_eq!(invocations.len(), 5);
+  for invocation in invocations {
+    let args: Vec<&str> = invocation.split_whitespace().map(|arg| arg.trim_matches('"')).collect();
+    assert!(io::try_parse_args(&argv(&args)).is_ok(), "{}", invocation);
+  }
+  assert!(options.starts_with("Usage: subquote"));
+  assert!(matches!(
+    io::try_parse_args(&argv(&["--help"])), Err(ParseOutcome::Help(usage)) if !usage.contains("Examples:")
+  ));
+  assert!(matches!(io::try_parse_args(&argv(&["--help-format", "man"])), Err(ParseOutcome::Error(_))));
+}
+
+#[test]
+fn rejects_an_empty_argv() {
+  assert!(matches!(io::try_parse_args(&[]), Err(ParseOutcome::Error(err)) if err.contains("program name")));
+}
+
+#[test]
+fn reports_a_negative_length() {
+  let dir = TempDir::new().unwrap();
+  let subtitle = write_subtitle(dir.path(), "show.srt", SUBTITLE);
+  let args = argv(&[subtitle.to_str().unwrap(), "--no-cache", "--length", "-1"]);
+  let errors = io::parse_args(&args).ok().unwrap().validate().err().unwrap();
+
+  assert!(errors.iter().any(|err| err.contains("quote length must be greater or equal to 0")));
+}
+
+#[test]
+fn reports_a_length_above_the_ceiling() {
+  for (opt, length) in [("--length", "2147483647"), ("--length", "5-1001"), ("--min-length", "1001")].iter() {
+    let args = argv(&["show.srt", opt, length]);
+    match io::parse_args(&args) {
+      Err(ParseOutcome::Error(err)) => assert!(err.starts_with("quote length can't be greater than 1000"), "{}", err),
+      _ => panic!("{} {} was accepted", opt, length)
+    }
+  }
+  assert!(io::parse_args(&argv(&["show.srt", "--length", &io::MAX_QUOTE_LENGTH.to_string()])).is_ok());
+}
+
+#[test]
+fn reports_an_unknown_template_placeholder() {
+  let dir = TempDir::new().unwrap();
+  let subtitle = write_subtitle(dir.path(), "show.srt", SUBTITLE);
+  let args = argv(&[subtitle.to_str().unwrap(), "--no-cache", "--template", "{quote} ({episode})"]);
+  let errors = io::parse_args(&args).ok().unwrap().validate().err().unwrap();
+
+  assert!(errors.iter().any(|err| err.contains("unknown template placeholder \"{episode}\"")));
+}
+
+#[test]
+fn continues_a_phrase_from_its_last_known_unit() {
+  let dict = builder::build_dict(SUBTITLE, &io::Unit::Word, false);
+  let config = QuoteConfig::new().length(2).seed(3);
+
+  assert_eq!(builder::generate_continuation(&dict, "Oh there", &config, false).unwrap(), "Oh there my friend.");
+  assert_eq!(builder::generate_continuation(&dict, "Say hello there, pal", &config, false).unwrap(), "Say hello there my friend.");
+  assert!(builder::generate_continuation(&dict, "Goodbye pal", &config, false).is_err());
+}
+
+#[test]
+fn keeps_the_continued_phrase_within_the_characters() {
+  let dir = TempDir::new().unwrap();
+  let subtitle = write_subtitle(dir.path(), "show.srt", SUBTITLE);
+  let quote_of = |phrase: &str, max_chars: &str| {
+    builder::get_quote(parsed(&subtitle, &["--no-cache", "--seed", "1", "--continue", phrase, "--max-chars", max_chars]))
+  };
+
+  let err = quote_of("this is a very long phrase that ends with Hello", "10").unwrap_err();
+  assert_eq!(err, "the continued phrase exceeds the 10 characters of the quote");
+  let quote = quote_of("Say hello there", "20").unwrap();
+  assert_eq!((quote.text.as_str(), quote.timecode), ("Say hello there my.", Some(Duration::from_secs(1))));
+}
+
+#[test]
+fn picks_lengths_within_a_range() {
+  let dict = builder::build_dict(&cues(&["A b c d e f g h i j"]), &io::Unit::Word, false);
+  let mut lengths: Vec<usize> = (0..50)
+    .map(|seed| {
+      let config = QuoteConfig::new().length_range(3, 6).seed(seed);
+      builder::generate(&dict, &config).unwrap().split(' ').count()
+    })
+    .collect();
+  lengths.sort();
+  lengths.dedup();
+
+  assert_eq!(lengths, vec![3, 4, 5, 6]);
+}
+
+fn quote_of(units: &[&str], separator: &str) -> String {
+  let mut dict = MarkovDict::new();
+  for pair in units.windows(2) {
+    dict.add_transition(pair[0], pair[1]);
+  }
+  let config = QuoteConfig::new().length(units.len() as i32).separator(separator);
+  builder::generate(&dict, &config).unwrap()
+}
+
+#[test]
+fn collapses_repeated_spaces() {
+  assert_eq!(quote_of(&["Hello", "  there ", "friend"], " "), "Hello there friend.");
+  assert_eq!(quote_of(&["H", "i", " ", "  ", "y", "o", "u"], ""), "Hi you.");
+}
+
+#[test]
+fn trims_the_quote() {
+  assert_eq!(quote_of(&["Hello ", "there", " "], " "), "Hello there.");
+}
+
+#[test]
+fn ends_quotes_with_a_period() {
+  assert_eq!(quote_of(&["Well", "then,"], " "), "Well then.");
+  assert_eq!(quote_of(&["Well", "then", ","], " "), "Well then.");
+  assert_eq!(quote_of(&["Well", "then."], " "), "Well then.");
+  assert_eq!(quote_of(&["Well", "then!"], " "), "Well then!");
+  assert_eq!(quote_of(&["Well", "then?"], " "), "Well then?");
+  assert_eq!(quote_of(&["Well", "then"], " "), "Well then.");
+}
+
+#[test]
+fn keeps_sentence_ending_punctuation() {
+  assert_eq!(quote_of(&["Really", "?"], " "), "Really?");
+  assert_eq!(quote_of(&["Wait", "…"], " "), "Wait…");
+  assert_eq!(quote_of(&["Go", "now!"], " "), "Go now!");
+}
+
+#[test]
+fn capitalizes_the_first_letter() {
+  let continued = |line: &str, config: &QuoteConfig| {
+    let dict = builder::build_dict(&cues(&[line]), &io::Unit::Word, true);
+    let first = line.split_whitespace().next().unwrap();
+    builder::generate_continuation(&dict, first, config, true).unwrap()
+  };
+  let config = QuoteConfig::new().length(3);
+
+  assert_eq!(continued("élan vital", &config), "Élan vital.");
+  assert_eq!(continued("¿ ça va", &config), "¿ Ça va.");
+  assert_eq!(continued("ǳ ou", &config), "ǲ ou.");
+  assert_eq!(continued("ßa b", &config), "Ssa b.");
+  assert_eq!(continued("ﬁne day", &config), "Fine day.");
+  assert_eq!(continued("ᾳ b", &config), "ᾼ b.");
+  assert_eq!(continued("3 dogs", &config), "3 dogs.");
+  assert_eq!(continued("¿ 3 dogs", &config), "¿ 3 dogs.");
+  assert_eq!(continued("élan vital", &config.no_capitalize(true)), "élan vital.");
+}
+
+#[test]
+fn rejects_quotes_repeating_themselves() {
+  let dict = |lines: &[&str]| builder::build_dict(&cues(lines), &io::Unit::Word, false);
+  let config = |seed| QuoteConfig::new().length(5).seed(seed);
+  let (looping, varied) = (dict(&["No no no no no no"]), dict(&["No no no no no no", "No way out of here"]));
+
+  assert_eq!(builder::generate(&looping, &config(0)).unwrap(), "No no.");
+  let err = builder::generate(&looping, &config(0).min_diversity(0.6)).unwrap_err();
+  assert_eq!(err, "couldn't build a quote with a diversity of at least 0.6 (got 0.50)");
+  for seed in 0..8 {
+    assert_eq!(builder::generate(&varied, &config(seed).min_diversity(0.6)).unwrap(), "No way out of here.");
+  }
+  let args = argv(&["show.srt", "--min-diversity", "1.5"]);
+  let errors = io::parse_args(&args).ok().unwrap().validate().unwrap_err();
+  assert!(errors.iter().any(|err| err.contains("diversity")), "{:?}", errors);
+}
+
+#[test]
+fn ends_quotes_of_degenerate_dictionaries_before_they_loop() {
+  let dict = |transitions: &[(&str, &str)]| {
+    let mut dict = MarkovDict::new();
+    for (unit, next) in transitions.iter() {
+      dict.add_transition(unit, next);
+    }
+    dict
+  };
+  let config = || QuoteConfig::new().length(8).seed(1);
+  let single_keys = [dict(&[("Hello", "Hello")]), dict(&[("Hello", "there")])];
+  let two_keys = [
+    dict(&[("Well", "then"), ("then", "Well")]),
+    dict(&[("Well", "then"), ("then", "then")]),
+    dict(&[("Well", "then"), ("then", "go")])
+  ];
+
+  assert_eq!(builder::generate(&single_keys[0], &config()).unwrap(), "Hello.");
+  assert_eq!(builder::generate(&single_keys[1], &config()).unwrap(), "Hello there.");
+  for dict in two_keys.iter().take(2) {
+    assert_eq!(builder::generate(dict, &config()).unwrap(), "Well then.");
+    assert_eq!(builder::generate(dict, &config().length(0)).unwrap(), "Well then.");
+  }
+  assert_eq!(builder::generate(&two_keys[2], &config()).unwrap(), "Well then go.");
+  let err = builder::generate(&single_keys[0], &config().min_length(3)).unwrap_err();
+  assert_eq!(err, "couldn't build a quote of at least 3 units (got 1)");
+  // a single key is enough for the binary too, only an empty dictionary isn't
+  let args = SafeArguments { seed: Some(1), ..SafeArguments::default() };
+  assert_eq!(builder::quote_from(&single_keys[1], &args).unwrap().text, "Hello there.");
+  let err = builder::quote_from(&MarkovDict::new(), &args).unwrap_err();
+  assert_eq!(err, "subtitle has too little dialogue to generate a quote");
+}
+
+#[test]
+fn drops_the_spaces_of_the_char_chain_when_asked() {
+  let dir = TempDir::new().unwrap();
+  let subtitle = write_subtitle(dir.path(), "show.srt", &cues(&["Hello   there my friend"]));
+  let cache = cache_dir(dir.path());
+  let validated = |extra: &[&str]| {
+    validated(&subtitle, &[&["--cache", cache.to_str().unwrap(), "--seed", "2"], extra].concat())
+  };
+  let corpus_of = |extra: &[&str]| builder::get_corpus(&validated(extra).unwrap()).unwrap();
+
+  let spaced = corpus_of(&["--unit", "char"]);
+  assert_eq!(spaced.successors("e"), Some(vec![" ", "l", "n", "r"]));
+  assert_eq!(spaced.successors(" "), Some(vec!["f", "m", "t"]));
+  let dropped = corpus_of(&["--unit", "char", "--drop-spaces"]);
+  assert_eq!(dropped.successors("e"), Some(vec!["l", "m", "n", "r"]));
+  assert!(!dropped.contains(" "));
+  assert!(cache.join("show.srt.char-nospaces").is_file());
+  let quote = builder::get_quote(validated(&["--unit", "char", "--drop-spaces", "--length", "12"]).unwrap()).unwrap();
+  assert!(!quote.text.contains(' '), "{}", quote.text);
+  assert!(validated(&["--drop-spaces"]).is_err());
+  assert!(validated(&["--unit", "word,char", "--drop-spaces"]).is_ok());
+}
+
+#[test]
+fn tries_other_starts_when_a_quote_dead_ends() {
+  let dict = builder::build_dict(&cues(&["Hi there", "So long", "Well we go home now"]), &io::Unit::Word, false);
+  let config = |seed| QuoteConfig::new().length(5).seed(seed);
+
+  for seed in 0..16 {
+    assert_eq!(builder::generate(&dict, &config(seed).min_length(4)).unwrap(), "Well we go home now.");
+  }
+  let err = builder::generate(&dict, &config(0).min_length(6)).unwrap_err();
+  assert_eq!(err, "couldn't build a quote of at least 6 units (got 5)");
+}
+
+#[test]
+fn ends_quotes_on_the_given_word() {
+  let dir = TempDir::new().unwrap();
+  let subtitle = write_subtitle(dir.path(), "show.srt", &cues(&["Well we go home now", "They run home", "So here we go"]));
+  let quote_of = |extra: &[&str]| {
+    builder::get_quote(parsed(&subtitle, &[&["--no-cache"], extra].concat())).map(|quote| quote.text)
+  };
+  let dict = builder::build_dict(&cues(&["Well we go home now", "They run home"]), &Unit::Word, false);
+  let reverse = builder::reverse_dict(&dict);
+
+  for seed in 0..8 {
+    let quote = quote_of(&["--end", "HOME", "--seed", &seed.to_string()]).unwrap();
+    assert!(quote == "Well we go home." || quote == "So here we go home." || quote == "They run home.", "{}", quote);
+  }
+  assert_eq!(quote_of(&["--end", "home", "--length", "2", "--seed", "1"]).unwrap(), "Run home.");
+  let err = quote_of(&["--end", "now", "--length", "8", "--min-length", "7", "--seed", "0"]).unwrap_err();
+  assert_eq!(err, "couldn't build a quote of at least 7 units ending on \"now\" (got 6)");
+  assert_eq!(quote_of(&["--end", "cat"]).unwrap_err(), "couldn't find \"cat\" in the dictionary");
+  let config = QuoteConfig::new().length(5).max_chars(14).seed(3);
+  assert_eq!(builder::generate_ending(&dict, &reverse, "now", &config).unwrap(), "Go home now.");
+  let args = argv(&[subtitle.to_str().unwrap(), "--end", "home", "--must-include", "go"]);
+  assert!(io::parse_args(&args).ok().unwrap().validate().is_err());
+}
+
+#[test]
+fn stops_at_the_stop_words() {
+  let subtitle = cues(&["Good morning to you my friend", "Good night to all of you"]);
+  let dict = builder::build_dict(&subtitle, &io::Unit::Word, false);
+  let config = || QuoteConfig::new().length(10).stop_at(&["TO"]);
+
+  for seed in 0..4 {
+    let quote = builder::generate(&dict, &config().seed(seed)).unwrap();
+    assert!(quote == "Good morning to." || quote == "Good night to.", "{}", quote);
+  }
+  assert!(builder::generate(&dict, &config().min_length(4)).is_err());
+  for seed in 0..4 {
+    let quote = builder::generate(&dict, &config().stop_at(&["morning"]).min_length(3).seed(seed)).unwrap();
+    assert!(quote.starts_with("Good night to"), "{}", quote);
+  }
+}
+
+#[test]
+fn finds_the_longest_quote_without_repeated_pairs() {
+  let dict = builder::build_dict(&cues(&["I go up and I go down", "down we go up"]), &io::Unit::Word, false);
+
+  for seed in 0..4 {
+    let config = QuoteConfig::new().longest(true).seed(seed);
+    assert_eq!(builder::generate(&dict, &config).unwrap(), "I go down we go up and I.");
+  }
+  let args = argv(&["show.srt", "--longest", "--length", "3"]);
+  assert!(io::parse_args(&args).ok().unwrap().validate().is_err());
+}
+
+#[test]
+fn leaves_the_period_out_when_asked() {
+  let dict = builder::build_dict(&cues(&["Hello there my friend,"]), &io::Unit::Word, true);
+  let config = QuoteConfig::new().length(5).seed(1).no_append(true);
+
+  assert_eq!(builder::generate(&dict, &config).unwrap(), "Hello there my friend");
+  assert_eq!(builder::generate(&dict, &config.no_append(false)).unwrap(), "Hello there my friend.");
+}
+
+#[test]
+fn skips_excluded_start_words() {
+  let dict = builder::build_dict(&cues(&["The cat sat", "And then Tom left"]), &io::Unit::Word, false);
+
+  for seed in 0..10 {
+    let config = QuoteConfig::new().length(2).seed(seed).no_start_words(&["the", "and"]);
+    assert_eq!(builder::generate(&dict, &config).unwrap(), "Tom left.");
+  }
+  let config = QuoteConfig::new().length(2).no_start_words(&["the", "and", "tom"]);
+  assert!(builder::generate(&dict, &config).is_ok());
+}
+
+#[test]
+fn reverses_the_transitions_of_a_dictionary() {
+  let dict = builder::build_dict(&cues(&["So I love you", "I love cake"]), &io::Unit::Word, false);
+  let reverse = builder::reverse_dict(&dict);
+
+  assert_eq!(reverse.successors("love"), Some(vec!["I", "I"]));
+  assert_eq!(reverse.successors("cake"), Some(vec!["love"]));
+  assert_eq!(reverse.successors("So"), None);
+  assert_eq!(reverse.transitions(), dict.transitions());
+  assert_eq!(builder::reverse_dict(&reverse), dict);
+}
+
+#[test]
+fn builds_quotes_around_a_word() {
+  let dict = builder::build_dict(&cues(&["So I love you", "I love cake"]), &io::Unit::Word, false);
+  let reverse = builder::reverse_dict(&dict);
+
+  for seed in 0..10 {
+    let config = QuoteConfig::new().length(3).seed(seed);
+    let quote = builder::generate_around(&dict, &reverse, "LOVE", &config).unwrap();
+    assert!(quote == "I love you." || quote == "I love cake.", "got {}", quote);
+  }
+  assert!(builder::generate_around(&dict, &reverse, "hate", &QuoteConfig::new()).is_err());
+}
+
+#[test]
+fn avoids_immediate_repeats_when_possible() {
+  let dict = builder::build_dict(&cues(&["Ha ha ha ha ha ha stop"]), &io::Unit::Word, false);
+
+  for seed in 0..20 {
+    let config = QuoteConfig::new().length(10).seed(seed).no_immediate_repeat(true);
+    assert_eq!(builder::generate(&dict, &config).unwrap(), "Ha ha stop.");
+  }
+}
+
+#[test]
+fn lets_the_caller_choose_the_successors() {
+  let dict = builder::build_dict(&cues(&["Hello there", "Hello world"]), &io::Unit::Word, false);
+  let mut offered: Vec<(String, Vec<String>)> = Vec::new();
+  let quote = builder::generate_with(&dict, &QuoteConfig::new().length(2), |unit, successors| {
+    offered.push((unit.to_string(), successors.to_vec()));
+    successors.iter().position(|next| next == "world")
+  });
+
+  assert_eq!(quote.unwrap(), "Hello world.");
+  assert_eq!(offered, vec![(String::from("Hello"), vec![String::from("there"), String::from("world")])]);
+}
+
+#[test]
+fn leaves_pair_loops_and_repeats_the_only_successor() {
+  let dict = builder::build_dict(&cues(&["So you know you know you know right"]), &io::Unit::Word, false);
+  let looping = builder::build_dict(&cues(&["Ha ha ha"]), &io::Unit::Word, false);
+
+  for seed in 0..20 {
+    let config = QuoteConfig::new().length(10).seed(seed).no_immediate_repeat(true);
+    assert_eq!(builder::generate(&dict, &config).unwrap(), "So you know right.");
+    assert_eq!(builder::generate(&looping, &config).unwrap(), "Ha ha.");
+  }
+}
+
+#[test]
+fn merged_dictionaries_match_a_single_subtitle() {
+  let (first, second) = ("The cat sat on the mat", "The dog sat on the cat");
+  let dict = |lines: &[&str]| builder::build_dict(&cues(lines), &io::Unit::Word, false);
+  let merged: MarkovDict = builder::merge_dicts(vec![dict(&[first]), dict(&[second])]);
+
+  assert_eq!(merged, builder::merge_dicts(vec![dict(&[second]), dict(&[first])]));
+  assert_eq!(merged, dict(&[first, second]));
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn builds_the_same_corpus_in_parallel() {
+  let dir = TempDir::new().unwrap();
+  let lines = ["The cat sat on the mat", "The dog sat on the cat", "The cat ran", "A dog ran on the mat"];
+  let subtitles: Vec<PathBuf> = lines.iter().enumerate()
+    .map(|(index, line)| write_subtitle(dir.path(), &format!("{}.srt", index), &cues(&[line])))
+    .collect();
+  let mut args = vec!["--no-cache"];
+  args.extend(subtitles.iter().map(|subtitle| subtitle.to_str().unwrap()));
+  let args = valid(&args);
+  let sequential = builder::get_corpus_with(&args, &mut builder::DictCache::new(1)).unwrap();
+
+  assert_eq!(builder::get_corpus(&args).unwrap(), *sequential);
+}
+
+// the same seed gives the same quote whatever the way the dictionary came to be, and across versions
+#[test]
+fn seeded_quotes_dont_depend_on_the_dictionary_source() {
+  let (first, second) = (
+    "The cat sat on the mat and the dog sat on the cat",
+    "The dog ran on the road and the cat ran on the mat"
+  );
+  let dict = |lines: &[&str]| builder::build_dict(&cues(lines), &io::Unit::Word, false);
+  let built = dict(&[first, second]);
+  let mut bytes: Vec<u8> = Vec::new();
+  built.write_to(&mut bytes).unwrap();
+  let dicts = [
+    MarkovDict::read_from(bytes.as_slice()).unwrap(),
+    builder::merge_dicts(vec![dict(&[first]), dict(&[second])]),
+    builder::merge_dicts(vec![dict(&[second]), dict(&[first])]),
+    built
+  ];
+  let config = || QuoteConfig::new().unit(Unit::Word).length(8).seed(42);
+
+  for dict in dicts.iter() {
+    assert_eq!(builder::generate(dict, &config()).unwrap(), "The cat ran on the dog ran on.");
+  }
+}
+
+#[test]
+fn changes_the_case_of_the_output() {
+  assert_eq!(io::Case::Upper.apply("Straße, naïve"), "STRASSE, NAÏVE");
+  assert_eq!(io::Case::Lower.apply("ÉCOLE Ǆ"), "école ǆ");
+  assert_eq!(io::Case::Title.apply("hELLO  éCOLE friend."), "Hello  École Friend.");
+  assert_eq!(io::Case::Title.apply("ßa ǆungla"), "Ssa ǅungla");
+}
+
+#[test]
+fn refuses_several_output_cases() {
+  let dir = TempDir::new().unwrap();
+  let subtitle = write_subtitle(dir.path(), "show.srt", SUBTITLE);
+  let args = argv(&[subtitle.to_str().unwrap(), "--no-cache", "--uppercase", "--title-case"]);
+
+  let errors = io::parse_args(&args).ok().unwrap().validate().unwrap_err();
+  assert_eq!(errors.len(), 1);
+  assert!(errors[0].contains("cannot be used mutually"));
+}
+
+#[test]
+fn reads_arguments_from_response_files() {
+  let dir = TempDir::new().unwrap();
+  let subtitle = write_subtitle(dir.path(), "show.srt", SUBTITLE);
+  fs::create_dir(dir.path().join("nested")).unwrap();
+  fs::write(dir.path().join("nested").join("length"), "--length\n2\n").unwrap();
+  let options = write_subtitle(dir.path(), "options", "--no-cache --seed 7\n@nested/length\n");
+
+  let quote = builder::get_quote(valid(&["gen", &format!("@{}", options.display()), subtitle.to_str().unwrap()])).unwrap();
+  assert_eq!(quote.text, "Hello there.");
+}
+
+#[test]
+fn rejects_missing_and_recursive_response_files() {
+  let dir = TempDir::new().unwrap();
+  let looping = write_subtitle(dir.path(), "looping", "--no-cache @looping");
+
+  for file in [dir.path().join("missing"), looping].iter() {
+    let args = argv(&[&format!("@{}", file.display())]);
+    match io::parse_args(&args) {
+      Err(ParseOutcome::Error(err)) => assert!(err.contains(&file.display().to_string())),
+      _ => panic!("expected a parsing error")
+    }
+  }
+}
+
+#[test]
+fn writes_and_reads_back_a_dictionary() {
+  let subtitle = cues(&["Good morning sir", "Good night sir"]);
+  let dict = builder::build_dict(&subtitle, &io::Unit::Word, false);
+  let mut bytes: Vec<u8> = Vec::new();
+  dict.write_to(&mut bytes).unwrap();
+
+  let read = MarkovDict::read_from(bytes.as_slice()).unwrap();
+  assert_eq!(read, dict);
+  assert_eq!(read.timecode("Good"), Some(Duration::from_secs(0)));
+  assert!(MarkovDict::read_from(&bytes[..bytes.len() - 1]).is_err());
+}
+
+#[test]
+fn grows_a_corpus_cache_across_runs() {
+  let dir = TempDir::new().unwrap();
+  let first = write_subtitle(dir.path(), "first.srt", &cues(&["Good morning sir"]));
+  let second = write_subtitle(dir.path(), "second.srt", &cues(&["Good night madam"]));
+  let cache = cache_dir(dir.path());
+  let corpus = dir.path().join("corpus.json");
+
+  let build = |subtitle: &Path| {
+    builder::build_caches(valid(&[
+      "build", subtitle.to_str().unwrap(), "--cache", cache.to_str().unwrap(), "--append-cache", corpus.to_str().unwrap()
+    ])).unwrap()
+  };
+  assert_eq!(build(&first).last(), Some(&corpus));
+  build(&second);
+
+  let dict = MarkovDict::read_from(fs::File::open(&corpus).unwrap()).unwrap();
+  assert_eq!(dict.successors("Good"), Some(vec!["morning", "night"]));
+  assert!(dict.contains("night") && dict.contains("morning"));
+  // generating reads the corpus without growing it
+  let args = parsed(&second, &["--cache-file", corpus.to_str().unwrap()]);
+  for _ in 0..2 {
+    assert_eq!(builder::get_corpus(&args).unwrap(), dict);
+  }
+  assert_eq!(MarkovDict::read_from(fs::File::open(&corpus).unwrap()).unwrap(), dict);
+  let args = argv(&[second.to_str().unwrap(), "--append-cache", corpus.to_str().unwrap()]);
+  assert!(matches!(io::parse_args(&args), Err(ParseOutcome::Error(_))));
+}
+
+#[test]
+fn caches_the_merged_corpus_in_the_cache_file() {
+  let dir = TempDir::new().unwrap();
+  let first = write_subtitle(dir.path(), "first.srt", &cues(&["Good morning sir"]));
+  let second = write_subtitle(dir.path(), "second.srt", &cues(&["Good night madam"]));
+  let cache_file = dir.path().join("shared.cache");
+  let args = |extra: &[&str]| {
+    validated(&first, &[&[second.to_str().unwrap(), "--cache-file", cache_file.to_str().unwrap()], extra].concat())
+  };
+
+  let caches = builder::build_caches(args(&[]).unwrap()).unwrap();
+  assert_eq!(caches, vec![cache_file.clone()]);
+  assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 3);
+  // the corpus comes from the cache file as long as it's there
+  fs::write(&second, cues(&["Good evening madam"])).unwrap();
+  let corpus = builder::get_corpus(&args(&[]).unwrap()).unwrap();
+  assert_eq!(corpus.successors("Good"), Some(vec!["morning", "night"]));
+  let mut dicts = builder::DictCache::new(1);
+  assert_eq!(*builder::get_corpus_with(&args(&[]).unwrap(), &mut dicts).unwrap(), corpus);
+
+  assert!(args(&["--no-cache"]).is_err());
+  assert!(args(&["--unit", "word,char"]).is_err());
+  let missing = dir.path().join("missing").join("shared.cache");
+  let args = argv(&[first.to_str().unwrap(), "--cache-file", missing.to_str().unwrap()]);
+  assert_eq!(
+    io::parse_args(&args).ok().unwrap().validate().unwrap_err(),
+    vec![format!("couldn't write to specified cache file (got \"{}\")", missing.display())]
+  );
+}
+
+#[test]
+fn verifies_the_caches_of_the_cache_directory() {
+  let dir = TempDir::new().unwrap();
+  let subtitle = write_subtitle(dir.path(), "show.srt", SUBTITLE);
+  let cache = cache_dir(dir.path());
+  builder::build_caches(valid(&["build", subtitle.to_str().unwrap(), "--cache", cache.to_str().unwrap()])).unwrap();
+  fs::write(cache.join("old.srt.word"), r#"{"entries":[{"key":"Hello","pairs":["there"]}]}"#).unwrap();
+  fs::write(cache.join("torn.srt.word"), r#"{"entries":[{"key":"#).unwrap();
+
+  let args = valid(&["verify", "--cache", cache.to_str().unwrap()]);
+  let caches: Vec<(String, CacheStatus)> = builder::verify_caches(&args).unwrap().into_iter()
+    .map(|(path, status)| (path.file_name().unwrap().to_string_lossy().into_owned(), status))
+    .collect();
+  assert_eq!(caches, vec![
+    (String::from("old.srt.word"), CacheStatus::Outdated),
+    (String::from("show.srt.word"), CacheStatus::Healthy),
+    (String::from("torn.srt.word"), CacheStatus::Broken(String::from("couldn't deserialize dictionary")))
+  ]);
+  let verify = || Command::new(env!("CARGO_BIN_EXE_subquote"))
+    .args(["verify", "--cache", cache.to_str().unwrap()])
+    .output()
+    .unwrap();
+  let output = verify();
+  assert_eq!(output.status.code(), Some(3));
+  assert!(String::from_utf8(output.stdout).unwrap().ends_with("1 healthy caches, 1 outdated, 1 that can't be used\n"));
+  // an outdated cache is only a warning
+  fs::remove_file(cache.join("torn.srt.word")).unwrap();
+  let output = verify();
+  assert!(output.status.success());
+  assert_eq!(
+    String::from_utf8(output.stdout).unwrap(),
+    format!(
+      "{}: warning: outdated cache written before timecodes were recorded\n1 healthy caches, 1 outdated, 0 that can't be used\n",
+      cache.join("old.srt.word").display()
+    )
+  );
+  assert!(io::parse_args(&argv(&["verify", subtitle.to_str().unwrap()])).ok().unwrap().validate().is_err());
+}
+
+#[test]
+fn previews_candidates_to_pick_from() {
+  let dir = TempDir::new().unwrap();
+  let subtitle = write_subtitle(dir.path(), "show.srt", SUBTITLE);
+  let run = |extra: &[&str]| {
+    let output = Command::new(env!("CARGO_BIN_EXE_subquote"))
+      .args([subtitle.to_str().unwrap(), "--no-cache", "--seed", "3", "--preview", "4"])
+      .args(extra)
+      .output()
+      .unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+  };
+
+  let preview = run(&[]);
+  let candidates: Vec<&str> = preview.lines().collect();
+  assert_eq!(candidates.len(), 4);
+  for (index, candidate) in candidates.iter().enumerate() {
+    let (number, quote) = candidate.split_once(". ").unwrap();
+    assert_eq!(number, (index + 1).to_string());
+    assert_eq!(run(&["--pick", number]), format!("{}\n", quote));
+  }
+  for (extra, valid) in [(&["--pick", "2"][..], false), (&["--preview", "0"], false), (&["--preview", "2"], true)].iter() {
+    let args = argv(&[&[subtitle.to_str().unwrap(), "--no-cache"], *extra].concat());
+    assert_eq!(io::parse_args(&args).ok().unwrap().validate().is_ok(), *valid, "{:?}", extra);
+  }
+  let args = argv(&[subtitle.to_str().unwrap(), "--no-cache", "--seed", "3", "--preview", "4", "--pick", "5"]);
+  assert!(io::parse_args(&args).ok().unwrap().validate().is_err());
+}
+
+#[test]
+fn lists_the_successors_of_a_word() {
+  let dir = TempDir::new().unwrap();
+  let lines = cues(&["We go home", "We go out", "We stay home", "We go"]);
+  let subtitle = write_subtitle(dir.path(), "show.srt", &lines);
+  let dict = builder::build_dict(&lines, &Unit::Word, false);
+  let run = |word: &str| {
+    Command::new(env!("CARGO_BIN_EXE_subquote"))
+      .args([subtitle.to_str().unwrap(), "--no-cache", "--successors", word])
+      .output()
+      .unwrap()
+  };
+
+  assert_eq!(dict.successor_counts("We"), Some(vec![("go", 3), ("stay", 1)]));
+  assert_eq!(dict.successor_counts("go"), Some(vec![("home", 1), ("out", 1)]));
+  assert_eq!(dict.successor_counts("home"), None);
+  let output = run("We");
+  assert!(output.status.success());
+  assert_eq!(String::from_utf8(output.stdout).unwrap(), "3 go\n1 stay\n");
+  let output = run("home");
+  assert_eq!(output.status.code(), Some(4));
+  assert!(String::from_utf8(output.stdout).unwrap().contains("isn't followed by anything"));
+  let args = argv(&[subtitle.to_str().unwrap(), "--no-cache", "--successors", "We", "--repl"]);
+  assert!(io::parse_args(&args).ok().unwrap().validate().is_err());
+}
+
+#[test]
+fn keeps_loaded_dictionaries_in_memory() {
+  let dir = TempDir::new().unwrap();
+  let first = write_subtitle(dir.path(), "first.srt", SUBTITLE);
+  let second = write_subtitle(dir.path(), "second.srt", &cues(&["Good morning sir"]));
+  let cache = cache_dir(dir.path());
+  let arguments = |subtitle: &Path| {
+    parsed(subtitle, &["--cache", cache.to_str().unwrap(), "--seed", "7"])
+  };
+  let mut dicts = builder::DictCache::new(1);
+
+  // the first call writes the cache, the next ones load it through the in-memory cache
+  for _ in 0..3 {
+    assert_eq!(builder::get_quote_with(&arguments(&first), &mut dicts).unwrap().text, "Hello there my friend.");
+  }
+  assert_eq!(dicts.len(), 1);
+  assert_eq!(builder::get_quote_with(&arguments(&second), &mut dicts).unwrap().text, "Good morning sir.");
+  builder::get_quote_with(&arguments(&second), &mut dicts).unwrap();
+  assert_eq!(dicts.len(), 1);
+  // a hit hands out the loaded dictionary itself
+  let loaded = dicts.load(&cache.join("second.srt.word")).unwrap();
+  assert!(Arc::ptr_eq(&loaded, &builder::get_corpus_with(&arguments(&second), &mut dicts).unwrap()));
+}
+
+#[test]
+fn splits_sentences_at_punctuation_when_asked() {
+  let dir = TempDir::new().unwrap();
+  let subtitle = write_subtitle(dir.path(), "show.srt", &cues(&["well then. we go home now!", "Maybe"]));
+  let cache = cache_dir(dir.path());
+  let safe_arguments = parsed(&subtitle, &["--cache", cache.to_str().unwrap(), "--punctuation-as-boundary", "--length", "max"]);
+  let dict = builder::get_corpus(&safe_arguments).unwrap();
+
+  assert_eq!(dict.successors("then"), None);
+  assert!(dict.is_starter("well") && dict.is_starter("we") && !dict.is_starter("Maybe"));
+  assert!(cache.join("show.srt.word-sentences").is_file());
+  let quote = builder::get_quote(safe_arguments).unwrap();
+  assert!(quote.text == "Well then." || quote.text == "We go home now.", "{}", quote.text);
+}
+
+#[test]
+fn starts_quotes_at_cues_when_asked() {
+  let dir = TempDir::new().unwrap();
+  let subtitle = write_subtitle(dir.path(), "show.srt", &cues(&["well then. We go home now", "so Maybe not"]));
+  let cache = cache_dir(dir.path());
+  let corpus_of = |extra: &[&str]| {
+    builder::get_corpus(&parsed(&subtitle, &[&["--cache", cache.to_str().unwrap(), "--cue-boundaries"], extra].concat())).unwrap()
+  };
+
+  let dict = corpus_of(&[]);
+  assert!(dict.is_starter("well") && dict.is_starter("so"));
+  assert!(!dict.is_starter("We") && !dict.is_starter("Maybe"));
+  assert_eq!(dict.successors("now"), None);
+  assert!(cache.join("show.srt.word-cues").is_file());
+  let dict = corpus_of(&["--punctuation-as-boundary"]);
+  assert!(dict.is_starter("well") && dict.is_starter("We") && dict.is_starter("so"));
+  assert!(cache.join("show.srt.word-sentences-cues").is_file());
+}
+
+#[test]
+fn keeps_the_lines_of_one_speaker() {
+  let dir = TempDir::new().unwrap();
+  let subtitle = write_subtitle(dir.path(), "show.srt", &cues(&[
+    "[BOB] Good morning sir", "ALICE: Good evening madam", "(Bob) Good night\nsir\n- Bye now", "<v Bob>See you</v>"
+  ]));
+  let ass = "[Events]\nFormat: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n\
+    Dialogue: 0,0:00:01.00,0:00:02.00,Default,Alice,0,0,0,,Hello there\n\
+    Dialogue: 0,0:00:03.00,0:00:04.00,Default,Bob,0,0,0,,Good bye\n";
+  let ass = write_subtitle(dir.path(), "show.ass", ass);
+  let dialogue_of = |subtitle: &Path| {
+    builder::get_dialogue(parsed(subtitle, &["--no-cache", "--dump-dialogue", "--speaker", "bob"])).unwrap()
+  };
+
+  assert_eq!(dialogue_of(&subtitle), "Good morning sir\nGood night\nsir\nSee you");
+  assert_eq!(dialogue_of(&ass), "Good bye");
+  let args = argv(&[subtitle.to_str().unwrap(), "--speaker", " "]);
+  assert!(io::parse_args(&args).ok().unwrap().validate().is_err());
+}
+
+#[test]
+fn splits_words_with_the_chosen_tokenizer() {
+  let build = |line: &str, tokenizer: &Tokenizer| {
+    let options = BuildOptions { tokenizer: tokenizer.clone(), ..BuildOptions::default() };
+    builder::build_dict_between(&cues(&[line]), &Unit::Word, &options)
+  };
+  let pattern = Tokenizer::Pattern(regex::Regex::new("/").unwrap());
+
+  assert_eq!(build("東京へ行く", &Tokenizer::UnicodeWords).successors("東"), Some(vec!["京"]));
+  assert_eq!(build("東京へ行く", &Tokenizer::Whitespace).len(), 0);
+  assert_eq!(build("good morning/ sir", &pattern).successors("good morning"), Some(vec!["sir"]));
+
+  let dir = TempDir::new().unwrap();
+  let subtitle = write_subtitle(dir.path(), "show.srt", &cues(&["Good morning sir"]));
+  let cache = cache_dir(dir.path());
+  builder::get_quote(parsed(&subtitle, &["--cache", cache.to_str().unwrap(), "--tokenizer", "unicode-words"])).unwrap();
+  assert!(cache.join("show.srt.word-unicode").is_file());
+  assert!(io::parse_args(&argv(&["show.srt", "--tokenizer", "regex:["])).is_err());
+  let args = argv(&["show.srt", "--tokenizer", "unicode-words", "--unit", "char"]);
+  assert!(io::parse_args(&args).ok().unwrap().validate().is_err());
+}
+
+#[test]
+fn reads_other_subtitle_formats() {
+  let dir = TempDir::new().unwrap();
+  let ass = "[Script Info]\n\n[Events]\nFormat: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n\
+    Dialogue: 0,0:00:01.50,0:00:02.00,Default,,0,0,0,,{\\i1}Hello there,\\Nmy friend{\\i0}\n";
+  let subtitles = [
+    write_subtitle(dir.path(), "show.ass", ass),
+    write_subtitle(dir.path(), "show.txt", "\nHello there, my friend\n")
+  ];
+
+  for subtitle in subtitles.iter() {
+    let quote = builder::get_quote(parsed(subtitle, &["--no-cache", "--seed", "7"])).unwrap();
+    assert_eq!(quote.text, "Hello there my friend.");
+  }
+  assert!(builder::get_quote(parsed(&subtitles[1], &["--no-cache", "--format", "srt"])).is_err());
+  // plain text is read line by line, a line of digits being dialogue rather than a cue index
+  let dict = builder::build_dict_from_text("\n2 by 2\n  Hello there  \n", &Unit::Word, &BuildOptions::default());
+  assert_eq!((dict.successors("2"), dict.successors("Hello")), (Some(vec!["by"]), Some(vec!["there"])));
+}
+
+#[test]
+fn favours_productive_starters_with_smart_start() {
+  let mut dict = MarkovDict::new();
+  dict.add_transition("Alone", "here");
+  for next in ["a", "b", "c", "d", "e", "f", "g", "h", "i"].iter() {
+    dict.add_transition("Many", next);
+  }
+  let count = |smart_start: bool| (0..200)
+    .filter(|seed| {
+      let config = QuoteConfig::new().length(2).seed(*seed).smart_start(smart_start);
+      builder::generate(&dict, &config).unwrap().starts_with("Many")
+    })
+    .count();
+
+  assert!(count(true) > 160, "{}", count(true));
+  assert!(count(false) < 140, "{}", count(false));
+}
+
+#[test]
+fn prunes_keys_out_of_reach_of_the_starts() {
+  let subtitle = cues(&["Good morning to you all", "then some other words"]);
+  let mut dict = builder::build_dict(&subtitle, &Unit::Word, false);
+  let mut keys = dict.keys();
+  keys.sort();
+  assert_eq!(keys, vec!["Good", "morning", "other", "some", "then", "to", "you"]);
+  assert_eq!(dict.len(), 7);
+
+  assert_eq!(builder::prune_unreachable(&mut dict, 2), 4);
+  let mut keys = dict.keys();
+  keys.sort();
+  assert_eq!(keys, vec!["Good", "morning", "to"]);
+  assert_eq!(dict.len(), 3);
+
+  let dir = TempDir::new().unwrap();
+  let path = write_subtitle(dir.path(), "show.srt", &subtitle);
+  let cache = cache_dir(dir.path());
+  let quote = builder::get_quote(parsed(&path, &["--cache", cache.to_str().unwrap(), "--prune", "-l", "3", "--seed", "1"])).unwrap();
+  assert_eq!(quote.text, "Good morning to.");
+  assert!(cache.join("show.srt.word-pruned-3").is_file());
+  let args = argv(&[path.to_str().unwrap(), "--prune", "--continue", "then"]);
+  assert!(io::parse_args(&args).ok().unwrap().validate().is_err());
+}
+
+#[test]
+fn exports_the_dictionary_as_json() {
+  let dir = TempDir::new().unwrap();
+  let subtitle = write_subtitle(dir.path(), "show.srt", &cues(&["Good morning sir", "Good morning", "Good evening"]));
+  let export = dir.path().join("dict.json");
+  builder::build_caches(valid(&[
+    "build", subtitle.to_str().unwrap(), "--cache", dir.path().to_str().unwrap(), "--json-dict-out", export.to_str().unwrap()
+  ])).unwrap();
+
+  assert_eq!(
+    fs::read_to_string(&export).unwrap(),
+    "{\n  \"Good\": {\n    \"evening\": 1,\n    \"morning\": 2\n  },\n  \"morning\": {\n    \"sir\": 1\n  }\n}"
+  );
+}
+
+#[test]
+fn round_trips_dictionaries_through_cache_files() {
+  let dir = TempDir::new().unwrap();
+  let subtitle = write_subtitle(dir.path(), "show.srt", &cues(&["Good morning sir", "Good night sir", "Good night madam"]));
+  let cache = cache_dir(dir.path());
+  let safe_arguments = valid(&["build", subtitle.to_str().unwrap(), "--cache", cache.to_str().unwrap()]);
+  let built = builder::get_corpus(&safe_arguments).unwrap();
+
+  let loaded = MarkovDict::read_from(fs::File::open(cache.join("show.srt.word")).unwrap()).unwrap();
+  assert_eq!(loaded, built);
+  assert_eq!(loaded.successors("Good"), Some(vec!["morning", "night", "night"]));
+  assert_eq!(builder::get_corpus(&safe_arguments).unwrap(), built);
+}
+
+// how fast either format loads is measured in benches/generation.rs
+#[test]
+fn loads_binary_caches_with_the_binary_reader() {
+  let dir = TempDir::new().unwrap();
+  let subtitle = write_subtitle(dir.path(), "show.srt", &cues(&["Good morning sir"]));
+  let cache = cache_dir(dir.path());
+  let other = builder::build_dict(&cues(&["Hello there friend"]), &Unit::Word, false);
+  let mut bytes = Vec::new();
+  other.write_binary(&mut bytes).unwrap();
+  fs::write(cache.join("show.srt.word.bin"), bytes).unwrap();
+
+  let corpus = builder::get_corpus(&parsed(&subtitle, &["--cache", cache.to_str().unwrap(), "--dict-format", "binary"]));
+  assert_eq!(corpus.unwrap(), other);
+}
+
+#[test]
+fn caches_dictionaries_in_the_binary_format_when_asked() {
+  let dir = TempDir::new().unwrap();
+  let subtitle = write_subtitle(dir.path(), "show.srt", &cues(&["Good morning sir", "Good night sir", "Good night madam"]));
+  let cache = cache_dir(dir.path());
+  let arguments = |format: &str| {
+    parsed(&subtitle, &["--cache", cache.to_str().unwrap(), "--dict-format", format])
+  };
+  let (json, binary) = (arguments("json"), arguments("binary"));
+  let built = builder::get_corpus(&binary).unwrap();
+
+  let bytes = fs::read(cache.join("show.srt.word.bin")).unwrap();
+  assert_eq!(MarkovDict::read_binary(bytes.as_slice()).unwrap(), built);
+  assert!(MarkovDict::read_binary(&bytes[..bytes.len() - 1]).is_err());
+  assert_eq!(builder::get_corpus(&binary).unwrap(), built);
+  assert_eq!(builder::get_corpus(&json).unwrap(), built);
+  assert!(bytes.len() < fs::metadata(cache.join("show.srt.word")).unwrap().len() as usize);
+  // either format is loaded whatever the option, here from the verification of the cache directory
+  let verified = builder::verify_caches(&json).unwrap();
+  assert_eq!(verified.len(), 2);
+  assert!(verified.iter().all(|(_, status)| *status == CacheStatus::Healthy), "{:?}", verified);
+  let args = argv(&[subtitle.to_str().unwrap(), "--dict-format", "yaml"]);
+  assert!(matches!(io::parse_args(&args), Err(ParseOutcome::Error(_))));
+}
+
+#[test]
+fn merges_duplicate_keys_of_a_cache() {
+  let cache = r#"{"entries": [{"key": "Good", "pairs": ["night"]}, {"key": "Good", "pairs": ["morning"]}]}"#;
+  let dict = MarkovDict::read_from(cache.as_bytes()).unwrap();
+
+  assert_eq!(dict.successors("Good"), Some(vec!["morning", "night"]));
+}
+
+#[test]
+fn answers_commands_in_the_repl() {
+  let dir = TempDir::new().unwrap();
+  let subtitle = write_subtitle(dir.path(), "show.srt", SUBTITLE);
+  let mut repl = Command::new(env!("CARGO_BIN_EXE_subquote"))
+    .args([subtitle.to_str().unwrap(), "--no-cache", "--repl", "--seed", "7"])
+    .stdin(Stdio::piped())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::null())
+    .spawn()
+    .unwrap();
+  repl.stdin.take().unwrap().write_all(b"\n:len 2\n\n:start my\n:quit\n\n").unwrap();
+  let output = repl.wait_with_output().unwrap();
+
+  assert!(output.status.success());
+  assert_eq!(String::from_utf8(output.stdout).unwrap(), "Hello there my friend.\nHello there.\nMy friend.\n");
+  for extra in [&["--template", "{quote}"][..], &["--with-timecode"], &["--must-include", "my"], &["--end", "my"]].iter() {
+    let errors = validated(&subtitle, &[&["--no-cache", "--repl"], *extra].concat()).unwrap_err();
+    assert!(errors.iter().any(|err| err.starts_with("options \"repl\" and")), "{:?}", errors);
+  }
+}
+
+#[test]
+fn avoids_the_last_quotes_of_the_repl() {
+  let dir = TempDir::new().unwrap();
+  let run = |subtitle: &Path, input: &[u8]| {
+    let mut repl = Command::new(env!("CARGO_BIN_EXE_subquote"))
+      .args([subtitle.to_str().unwrap(), "--no-cache", "--repl", "--seed", "7", "--no-repeat-window", "1"])
+      .stdin(Stdio::piped())
+      .stdout(Stdio::piped())
+      .stderr(Stdio::piped())
+      .spawn()
+      .unwrap();
+    repl.stdin.take().unwrap().write_all(input).unwrap();
+    let output = repl.wait_with_output().unwrap();
+    (String::from_utf8(output.stdout).unwrap(), String::from_utf8(output.stderr).unwrap())
+  };
+
+  let (quotes, _) = run(&write_subtitle(dir.path(), "two.srt", &cues(&["Hello there", "Good bye"])), b"\n\n\n\n");
+  let quotes: Vec<&str> = quotes.lines().collect();
+  assert_eq!(quotes.len(), 4);
+  assert!(quotes.windows(2).all(|pair| pair[0] != pair[1]), "{:?}", quotes);
+  let (quotes, errors) = run(&write_subtitle(dir.path(), "one.srt", SUBTITLE), b"\n\n");
+  assert_eq!(quotes, "Hello there my friend.\n");
+  assert!(errors.contains("couldn't generate a quote other than the last 1"), "{}", errors);
+
+  let mut recent = builder::RecentQuotes::new(2);
+  for quote in ["a", "b", "c"].iter() {
+    recent.push(quote);
+  }
+  assert!(!recent.contains("a") && recent.contains("b") && recent.contains("c"));
+}
+
+#[test]
+fn traces_the_chain_on_stderr() {
+  let dir = TempDir::new().unwrap();
+  let subtitle = write_subtitle(dir.path(), "show.srt", SUBTITLE);
+  let output = Command::new(env!("CARGO_BIN_EXE_subquote"))
+    .args([subtitle.to_str().unwrap(), "--no-cache", "--trace", "--length", "max"])
+    .output()
+    .unwrap();
+
+  assert_eq!(String::from_utf8(output.stdout).unwrap(), "Hello there my friend.\n");
+  assert_eq!(
+    String::from_utf8(output.stderr).unwrap(),
+    "Hello -> there (1 candidate)\nthere -> my (1 candidate)\nmy -> friend (1 candidate)\nfriend (no successor)\n"
+  );
+}
+
+#[test]
+fn returns_the_traced_steps_to_the_caller() {
+  let dict = builder::build_dict(&cues(&["Good morning", "Good night"]), &Unit::Word, false);
+  let config = || QuoteConfig::new().length(2).seed(1);
+
+  let (quote, trace) = builder::generate_traced(&dict, &config().trace(true)).unwrap();
+  let next = quote.trim_end_matches('.').split(' ').nth(1).unwrap();
+  assert_eq!(trace, vec![format!("Good -> {} (2 candidates)", next)]);
+  assert_eq!(builder::generate_traced(&dict, &config()).unwrap(), (quote, Vec::new()));
+}
+
+#[test]
+fn writes_quotes_to_the_output_file() {
+  let dir = TempDir::new().unwrap();
+  let subtitle = write_subtitle(dir.path(), "show.srt", SUBTITLE);
+  let quotes = dir.path().join("quotes.txt");
+  let run = |extra: &[&str]| {
+    Command::new(env!("CARGO_BIN_EXE_subquote"))
+      .args([subtitle.to_str().unwrap(), "--no-cache", "--output-file", quotes.to_str().unwrap()])
+      .args(extra)
+      .output()
+      .unwrap()
+  };
+
+  let output = run(&[]);
+  assert!(output.status.success());
+  assert!(output.stdout.is_empty());
+  run(&["--append-output", "--uppercase"]);
+  assert_eq!(fs::read_to_string(&quotes).unwrap(), "Hello there my friend.\nHELLO THERE MY FRIEND.\n");
+  run(&["--min-length", "10"]);
+  assert_eq!(fs::read_to_string(&quotes).unwrap(), "Hello there my friend.\nHELLO THERE MY FRIEND.\n");
+  run(&[]);
+  assert_eq!(fs::read_to_string(&quotes).unwrap(), "Hello there my friend.\n");
+
+  let errors = io::parse_args(&argv(&["show.srt", "--append-output"])).ok().unwrap().validate().unwrap_err();
+  assert!(errors.contains(&String::from("option \"append-output\" can only be used with option \"output-file\"")));
+}
+
+#[test]
+fn times_each_stage_when_verbose() {
+  let dir = TempDir::new().unwrap();
+  let subtitle = write_subtitle(dir.path(), "show.srt", SUBTITLE);
+  let run = || {
+    let output = Command::new(env!("CARGO_BIN_EXE_subquote"))
+      .args([subtitle.to_str().unwrap(), "--cache", dir.path().to_str().unwrap(), "--verbose"])
+      .output()
+      .unwrap();
+    String::from_utf8(output.stderr).unwrap()
+  };
+  let stages = |stderr: &str| -> Vec<String> {
+    stderr.lines().filter_map(|line| line.rsplit_once(" in ")).map(|(stage, _)| String::from(stage)).collect()
+  };
+  let cache = dir.path().join("show.srt.word");
+
+  assert_eq!(stages(&run()), vec![
+    String::from("Parsed the arguments"),
+    String::from("Built the word dictionary"),
+    format!("Saved cache \"{}\"", cache.display()),
+    String::from("Generated the quote")
+  ]);
+  assert_eq!(stages(&run()), vec![
+    String::from("Parsed the arguments"),
+    format!("Loaded cache \"{}\"", cache.display()),
+    String::from("Generated the quote")
+  ]);
+}
+
+#[test]
+fn honours_short_and_long_option_names() {
+  let dir = TempDir::new().unwrap();
+  let subtitle = write_subtitle(dir.path(), "show.srt", SUBTITLE);
+  let cache = cache_dir(dir.path());
+
+  for (length, unit, verbose) in [("-l", "-u", "-v"), ("--length", "--unit", "--verbose")].iter() {
+    let safe_arguments = parsed(&subtitle, &["--cache", cache.to_str().unwrap(), length, "3", unit, "char", verbose]);
+    assert_eq!((safe_arguments.quote_length, safe_arguments.verbosity), (3, true));
+    assert!(safe_arguments.unit == io::Unit::Grapheme);
+    assert_eq!(safe_arguments.cache_directory, cache);
+
+    builder::get_quote(safe_arguments).unwrap();
+    assert!(cache.join("show.srt.char").is_file());
+  }
+}
+
+#[test]
+fn keeps_the_captions_when_asked() {
+  let dir = TempDir::new().unwrap();
+  let subtitle = write_subtitle(dir.path(), "show.srt", &cues(&["[BOB] Well (sighs) we go", "♪ la la ♪ home now"]));
+  let cache = cache_dir(dir.path());
+  let dialogue_of = |extra: &[&str]| {
+    builder::get_dialogue(parsed(&subtitle, &[&["--no-cache", "--dump-dialogue"], extra].concat())).unwrap()
+  };
+
+  assert_eq!(dialogue_of(&[]), "Well we go\nhome now");
+  assert_eq!(dialogue_of(&["--keep-captions"]), "Well (sighs) we go\n♪ la la ♪ home now");
+  builder::get_quote(parsed(&subtitle, &["--cache", cache.to_str().unwrap(), "--keep-captions"])).unwrap();
+  assert!(cache.join("show.srt.word-captions").is_file());
+}
@@ -0,0 +1,148 @@
+use std::path::Path;
+use std::time::Duration;
+use subquote::{
+  io::{Format, Unit},
+  builder
+};
+
+const SUBTITLE: &str = "1
+00:00:01,000 --> 00:00:02,000
+Hello there my friend
+
+2
+00:00:03,000 --> 00:00:04,000
+How are you today
+";
+
+fn assert_no_timing(lines: &[String]) {
+  for line in lines.iter() {
+    assert!(!line.contains("-->"), "timing line kept as dialogue: {:?}", line);
+  }
+}
+
+#[test]
+fn reads_windows_line_endings() {
+  let subtitle = SUBTITLE.replace('\n', "\r\n");
+
+  assert_eq!(builder::dialogue_lines(&subtitle), vec!["Hello there my friend", "How are you today"]);
+}
+
+#[test]
+fn ignores_byte_order_marks() {
+  let subtitle = format!("\u{feff}{}", SUBTITLE);
+
+  assert_eq!(builder::dialogue_lines(&subtitle), vec!["Hello there my friend", "How are you today"]);
+}
+
+#[test]
+fn splits_cues_missing_their_blank_line() {
+  let subtitle = "1\n00:00:01,000 --> 00:00:02,000\nHello there\n2\n00:00:03,000 --> 00:00:04,000\nGood bye\n";
+
+  assert_eq!(builder::dialogue_lines(subtitle), vec!["Hello there", "Good bye"]);
+}
+
+#[test]
+fn accepts_loose_timecodes() {
+  let subtitle = "1\n0:00:01.5 --> 0:00:02.000 X1:10 X2:20\nHello there\n\n2\n00:00:03-->00:00:04\nGood bye\n";
+  let dict = builder::build_dict(subtitle, &Unit::Word, false);
+
+  assert_eq!(builder::dialogue_lines(subtitle), vec!["Hello there", "Good bye"]);
+  assert_eq!(dict.timecode("Hello"), Some(Duration::from_millis(1500)));
+  assert_eq!(dict.timecode("Good"), Some(Duration::from_secs(3)));
+}
+
+#[test]
+fn reads_webvtt_cues() {
+  let subtitle = "WEBVTT\n\nNOTE a comment\n\nintro\n00:01.000 --> 00:02.000 align:start\n<v Bob>Hello there</v>\n\n00:00:03.000 --> 00:00:04.000\nGood bye\n";
+
+  assert_eq!(builder::dialogue_lines(subtitle), vec!["Hello there", "Good bye"]);
+}
+
+#[test]
+fn keeps_overlapping_cues() {
+  let subtitle = "1\n00:00:01,000 --> 00:00:05,000\nHello there\n\n2\n00:00:02,000 --> 00:00:03,000\nGood bye\n";
+
+  assert_eq!(builder::dialogue_lines(subtitle), vec!["Hello there", "Good bye"]);
+}
+
+#[test]
+fn drops_malformed_timing_lines() {
+  let subtitles = [
+    "1\n00:00:01,000 --> 00:00:02,000\nHello there\n00:00:0x,000 --> 00:00:02,000\n",
+    "1\n00:00:01,000 --> 00:00:02,000\nHello there\n99999999999999999999:00:00,000 --> 00:00:02,000\n",
+    "1\n00:00:01,000 --> 00:00:02,000\nHello there\n--> 00:00:02,000\n"
+  ];
+
+  for subtitle in subtitles.iter() {
+    let lines = builder::dialogue_lines(subtitle);
+    assert_no_timing(&lines);
+    assert_eq!(lines, vec!["Hello there"]);
+  }
+}
+
+#[test]
+fn survives_truncated_and_garbled_input() {
+  let subtitle = SUBTITLE.replace('\n', "\r\n");
+  let garbled = ["", "\n\n\n", "-->", "1\n2\n3\n", "\u{feff}", "1\n00:00:01,000 -->", "\0\u{fffd}\r\r"];
+  let truncated = subtitle.char_indices().map(|(index, _)| &subtitle[..index]);
+  let shuffled = subtitle.lines().rev().collect::<Vec<&str>>().join("\n");
+
+  for input in garbled.iter().cloned().chain(truncated).chain(Some(shuffled.as_str())) {
+    assert_no_timing(&builder::dialogue_lines(input));
+    for unit in [Unit::Word, Unit::Grapheme].iter() {
+      builder::build_dict(input, unit, true);
+    }
+  }
+}
+
+#[test]
+fn links_the_lines_of_a_cue() {
+  let subtitle = "1\n00:00:01,000 --> 00:00:02,000\nI told you\nto wait here\n\n2\n00:00:03,000 --> 00:00:04,000\n- Who are you\n- Nobody\n";
+  let dict = builder::build_dict(subtitle, &Unit::Word, false);
+
+  assert_eq!(dict.successors("you"), Some(vec!["to"]));
+  assert_eq!(dict.successors("Who"), Some(vec!["are"]));
+}
+
+#[test]
+fn exports_the_common_types_in_the_prelude() {
+  use subquote::prelude::*;
+
+  let dict: MarkovDict = build_dict(SUBTITLE, &Unit::Word, false);
+  let quote = generate(&dict, &QuoteConfig::new().unit(Unit::Word).length(4).seed(1)).unwrap();
+  assert!(quote == "Hello there my friend." || quote == "How are you today.", "{}", quote);
+  assert_eq!(builder::sniff_format(Path::new("show.srt"), SUBTITLE), Format::Srt);
+}
+
+#[test]
+fn sniffs_the_subtitle_format() {
+  let ass = "[Script Info]\nTitle: show\n\n[Events]\nDialogue: 0,0:00:01.00,0:00:02.00,Default,,0,0,0,,Hello";
+
+  assert_eq!(builder::sniff_format(Path::new("show.vtt"), SUBTITLE), Format::Vtt);
+  assert_eq!(builder::sniff_format(Path::new("show.ASS.gz"), SUBTITLE), Format::Ass);
+  assert_eq!(builder::sniff_format(Path::new("show"), "WEBVTT\n\n00:01.000 --> 00:02.000\nHello"), Format::Vtt);
+  assert_eq!(builder::sniff_format(Path::new("show.txt"), ass), Format::Ass);
+  assert_eq!(builder::sniff_format(Path::new("show.sub"), &format!("\u{feff}{}", SUBTITLE)), Format::Srt);
+  assert_eq!(builder::sniff_format(Path::new("show.txt"), "Hello there\nGood bye"), Format::Text);
+}
+
+#[test]
+fn strips_each_caption_style() {
+  let captions = [
+    ("[door slams] Who is there", "Who is there"),
+    ("I told you (quietly) to wait here", "I told you to wait here"),
+    ("You (sighs) never listen (laughs)", "You never listen"),
+    ("So [thunder] we go [rain] home", "So we go home"),
+    ("♪ la la la ♪ Hello there", "Hello there"),
+    ("Hello ♪ humming", "Hello"),
+    ("# singing along # Good bye", "Good bye"),
+    ("We're #1 again", "We're #1 again")
+  ];
+
+  for (line, dialogue) in captions.iter() {
+    let subtitle = format!("1\n00:00:01,000 --> 00:00:02,000\n{}\n", line);
+    assert_eq!(builder::dialogue_lines(&subtitle), vec![*dialogue], "{}", line);
+  }
+  let subtitle = "1\n00:00:01,000 --> 00:00:02,000\n♪ ♪\n[music]\n";
+  assert!(builder::dialogue_lines(subtitle).is_empty());
+}